@@ -1,115 +1,161 @@
-use openssl::symm;
+#[cfg(feature = "openssl")]
+pub mod ossl;
+pub mod soft;
 
-use crate::utils::pkcs_7_padding;
+use crate::error::{Error, Result};
+use crate::utils::{pkcs_7_padding, pkcs_7_padding_validate};
 use crate::xor::xor;
 
-pub fn decrypt_128_ecb(key: &[u8], data: &[u8], pad: bool) -> Vec<u8> {
-    assert!(key.len() == 16);
-    assert!(
-        pad || data.len() % 16 == 0,
-        "Data must be multiple of 16 bytes if not padding is enabled"
-    );
+pub trait BlockCipher: Sized {
+    const BLOCK_SIZE: usize;
+    const KEY_SIZE: usize;
 
-    let mut c =
-        symm::Crypter::new(symm::Cipher::aes_128_ecb(), symm::Mode::Decrypt, key, None).unwrap();
-    c.pad(pad);
+    fn new(key: &[u8]) -> Result<Self>;
 
-    let mut plaintext = vec![0; data.len() + symm::Cipher::aes_128_ecb().block_size()];
-    let count = c.update(data, &mut plaintext).unwrap();
-    let rest = c.finalize(&mut plaintext[count..]).unwrap();
-    plaintext.truncate(count + rest);
+    fn encrypt_block(&self, block: &[u8]) -> Result<Vec<u8>>;
 
-    plaintext
+    fn decrypt_block(&self, block: &[u8]) -> Result<Vec<u8>>;
 }
 
-pub fn encrypt_128_ecb(key: &[u8], data: &[u8], pad: bool) -> Vec<u8> {
-    assert!(key.len() == 16, "Key must have length 16 bytes");
-    assert!(
-        pad || data.len() % 16 == 0,
-        "Data must be multiple of 16 bytes if not padding is enabled"
-    );
-    let mut c =
-        symm::Crypter::new(symm::Cipher::aes_128_ecb(), symm::Mode::Encrypt, key, None).unwrap();
-    c.pad(pad);
-
-    let mut ciphertext = vec![0; data.len() + symm::Cipher::aes_128_ecb().block_size()];
-    let count = c.update(data, &mut ciphertext).unwrap();
-    let rest = c.finalize(&mut ciphertext[count..]).unwrap();
-    ciphertext.truncate(count + rest);
-
-    ciphertext
+// OpenSSL is used for the block operation unless the `soft-aes` feature is
+// enabled or the crate is built without the `openssl` feature.
+#[cfg(all(feature = "openssl", not(feature = "soft-aes")))]
+pub use self::ossl::{Aes128, Aes192, Aes256};
+#[cfg(any(feature = "soft-aes", not(feature = "openssl")))]
+pub use self::soft::{Aes128, Aes192, Aes256};
+
+fn check_iv<C: BlockCipher>(iv: &[u8]) -> Result<()> {
+    if iv.len() != C::BLOCK_SIZE {
+        return Err(Error::InvalidIvLength(iv.len()));
+    }
+    Ok(())
+}
+
+pub fn encrypt_ecb<C: BlockCipher>(cipher: &C, data: &[u8], pad: bool) -> Result<Vec<u8>> {
+    // Data must be multiple of the block size if not padding is enabled
+    if !pad && !data.len().is_multiple_of(C::BLOCK_SIZE) {
+        return Err(Error::InvalidDataLength(data.len()));
+    }
+
+    let plaintext = if pad {
+        pkcs_7_padding(data, C::BLOCK_SIZE)
+    } else {
+        data.to_owned()
+    };
+
+    let mut output = Vec::with_capacity(plaintext.len());
+
+    for chunk in plaintext.chunks(C::BLOCK_SIZE) {
+        output.extend_from_slice(&cipher.encrypt_block(chunk)?);
+    }
+
+    Ok(output)
+}
+
+pub fn decrypt_ecb<C: BlockCipher>(cipher: &C, data: &[u8], pad: bool) -> Result<Vec<u8>> {
+    if !data.len().is_multiple_of(C::BLOCK_SIZE) {
+        return Err(Error::InvalidDataLength(data.len()));
+    }
+
+    let mut output = Vec::with_capacity(data.len());
+
+    for chunk in data.chunks(C::BLOCK_SIZE) {
+        output.extend_from_slice(&cipher.decrypt_block(chunk)?);
+    }
+
+    if pad {
+        pkcs_7_padding_validate(&output, C::BLOCK_SIZE)
+    } else {
+        Ok(output)
+    }
 }
 
-pub fn encrypt_128_cbc(key: &[u8], data: &[u8], iv: &[u8], pad: bool) -> Vec<u8> {
-    assert!(iv.len() == 16, "IV must have length 16 bytes");
-    assert!(key.len() == 16, "Key must have length 16 bytes");
-    assert!(
-        pad || data.len() % 16 == 0,
-        "Data must be multiple of 16 bytes or padding must be enabled"
-    );
+pub fn encrypt_cbc<C: BlockCipher>(
+    cipher: &C,
+    data: &[u8],
+    iv: &[u8],
+    pad: bool,
+) -> Result<Vec<u8>> {
+    check_iv::<C>(iv)?;
+    // Data must be multiple of the block size or padding must be enabled
+    if !pad && !data.len().is_multiple_of(C::BLOCK_SIZE) {
+        return Err(Error::InvalidDataLength(data.len()));
+    }
 
     let mut prev = iv.to_owned();
 
     let mut output = Vec::new();
 
     let plaintext = if pad {
-        pkcs_7_padding(data, 16)
+        pkcs_7_padding(data, C::BLOCK_SIZE)
     } else {
         data.to_owned()
     };
 
-    assert!(plaintext.len() % 16 == 0);
-
-    for chunk in plaintext.chunks(16) {
-        assert!(chunk.len() == 16);
-        assert!(prev.len() == 16);
+    for chunk in plaintext.chunks(C::BLOCK_SIZE) {
         let mixed = xor(chunk, &prev);
-        assert!(mixed.len() == 16);
-        let encrypted = encrypt_128_ecb(key, &mixed, false);
-        assert!(encrypted.len() == 16);
+        let encrypted = cipher.encrypt_block(&mixed)?;
 
         output.extend_from_slice(&encrypted);
 
         prev = encrypted;
     }
 
-    output
+    Ok(output)
 }
 
-pub fn decrypt_128_cbc(key: &[u8], data: &[u8], iv: &[u8]) -> Vec<u8> {
-    assert!(iv.len() == 16, "IV must have length 16 bytes");
-    assert!(key.len() == 16, "Key must have length 16 bytes");
-    assert!(data.len() % 16 == 0, "Data must be multiple of 16 bytes");
+pub fn decrypt_cbc<C: BlockCipher>(cipher: &C, data: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
+    check_iv::<C>(iv)?;
+    if !data.len().is_multiple_of(C::BLOCK_SIZE) {
+        return Err(Error::InvalidDataLength(data.len()));
+    }
 
-    let mut prev = iv.to_owned();
+    let mut prev = iv;
 
     let mut output = Vec::new();
 
-    for chunk in data.chunks(16) {
-        let decrypted = decrypt_128_ecb(key, chunk, false);
-        let mixed = xor(&decrypted, &prev);
+    for chunk in data.chunks(C::BLOCK_SIZE) {
+        let decrypted = cipher.decrypt_block(chunk)?;
+        let mixed = xor(&decrypted, prev);
 
         output.extend_from_slice(&mixed);
 
-        prev = chunk.to_owned();
+        prev = chunk;
     }
 
-    output
+    Ok(output)
+}
+
+pub fn decrypt_128_ecb(key: &[u8], data: &[u8], pad: bool) -> Result<Vec<u8>> {
+    decrypt_ecb(&Aes128::new(key)?, data, pad)
+}
+
+pub fn encrypt_128_ecb(key: &[u8], data: &[u8], pad: bool) -> Result<Vec<u8>> {
+    encrypt_ecb(&Aes128::new(key)?, data, pad)
+}
+
+pub fn encrypt_128_cbc(key: &[u8], data: &[u8], iv: &[u8], pad: bool) -> Result<Vec<u8>> {
+    encrypt_cbc(&Aes128::new(key)?, data, iv, pad)
+}
+
+pub fn decrypt_128_cbc(key: &[u8], data: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
+    decrypt_cbc(&Aes128::new(key)?, data, iv)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::{decode_hex, encode_hex};
 
     #[test]
     fn test_encrypt_decrypt_ecb() {
         let key: Vec<u8> = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 1, 2, 3, 4, 5, 6];
         let input: Vec<u8> = vec![255, 0, 128, 0, 64, 0, 32, 0, 255, 1, 128, 2, 64, 3, 32, 4];
         assert_eq!(input.len(), 16);
-        let encrypted = encrypt_128_ecb(&key, &input, false);
+        let encrypted = encrypt_128_ecb(&key, &input, false).unwrap();
 
         assert_eq!(encrypted.len(), 16);
-        let decrypted = decrypt_128_ecb(&key, &encrypted, false);
+        let decrypted = decrypt_128_ecb(&key, &encrypted, false).unwrap();
 
         assert_eq!(input, decrypted);
     }
@@ -122,10 +168,10 @@ mod tests {
             0, 255, 1, 128, 2, 64, 3, 32, 4,
         ];
         assert_eq!(input.len(), 32);
-        let encrypted = encrypt_128_ecb(&key, &input, false);
+        let encrypted = encrypt_128_ecb(&key, &input, false).unwrap();
 
         assert_eq!(encrypted.len(), 32);
-        let decrypted = decrypt_128_ecb(&key, &encrypted, false);
+        let decrypted = decrypt_128_ecb(&key, &encrypted, false).unwrap();
 
         assert_eq!(input, decrypted);
     }
@@ -138,10 +184,10 @@ mod tests {
             0, 255, 1, 128, 2, 64, 3, 32, 4, 1,
         ];
         assert_eq!(input.len(), 33);
-        let encrypted = encrypt_128_ecb(&key, &input, true);
+        let encrypted = encrypt_128_ecb(&key, &input, true).unwrap();
 
         assert_eq!(encrypted.len(), 48);
-        let decrypted = decrypt_128_ecb(&key, &encrypted, true);
+        let decrypted = decrypt_128_ecb(&key, &encrypted, true).unwrap();
         assert_eq!(decrypted.len(), 33);
 
         assert_eq!(input, decrypted);
@@ -156,9 +202,104 @@ mod tests {
         ];
         let iv: Vec<u8> = vec![0; 16];
 
-        let encrypted = encrypt_128_cbc(&key, &input, &iv, false);
-        let decrypted = decrypt_128_cbc(&key, &encrypted, &iv);
+        let encrypted = encrypt_128_cbc(&key, &input, &iv, false).unwrap();
+        let decrypted = decrypt_128_cbc(&key, &encrypted, &iv).unwrap();
 
         assert_eq!(input, decrypted);
     }
+
+    #[test]
+    fn test_invalid_key_and_iv_length() {
+        let key: Vec<u8> = vec![0; 15];
+        let input: Vec<u8> = vec![0; 16];
+        assert!(matches!(
+            encrypt_128_ecb(&key, &input, false),
+            Err(Error::InvalidKeyLength(15))
+        ));
+
+        let key: Vec<u8> = vec![0; 16];
+        let iv: Vec<u8> = vec![0; 8];
+        assert!(matches!(
+            decrypt_128_cbc(&key, &input, &iv),
+            Err(Error::InvalidIvLength(8))
+        ));
+        assert!(matches!(
+            encrypt_128_ecb(&key, &input[..15], false),
+            Err(Error::InvalidDataLength(15))
+        ));
+    }
+
+    #[test]
+    fn test_decrypt_ecb_bad_padding() {
+        let key: Vec<u8> = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 1, 2, 3, 4, 5, 6];
+        let input: Vec<u8> = vec![0; 16];
+        let encrypted = encrypt_128_ecb(&key, &input, false).unwrap();
+
+        assert!(matches!(
+            decrypt_128_ecb(&key, &encrypted, true),
+            Err(Error::BadPadding)
+        ));
+    }
+
+    fn fips_197_vector<C: BlockCipher>(key_hex: &str, expected_hex: &str) {
+        let key = decode_hex(key_hex).unwrap();
+        let plaintext = decode_hex("00112233445566778899aabbccddeeff").unwrap();
+        let cipher = C::new(&key).unwrap();
+
+        let encrypted = cipher.encrypt_block(&plaintext).unwrap();
+        assert_eq!(expected_hex, encode_hex(&encrypted));
+        assert_eq!(plaintext, cipher.decrypt_block(&encrypted).unwrap());
+    }
+
+    // The FIPS-197 appendix C vectors, shared with the backend tests
+    pub(crate) fn fips_197_appendix_c<A128, A192, A256>()
+    where
+        A128: BlockCipher,
+        A192: BlockCipher,
+        A256: BlockCipher,
+    {
+        fips_197_vector::<A128>(
+            "000102030405060708090a0b0c0d0e0f",
+            "69c4e0d86a7b0430d8cdb78070b4c55a",
+        );
+        fips_197_vector::<A192>(
+            "000102030405060708090a0b0c0d0e0f1011121314151617",
+            "dda97ca4864cdfe06eaf70a0ec0d7191",
+        );
+        fips_197_vector::<A256>(
+            "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+            "8ea2b7ca516745bfeafc49904b496089",
+        );
+    }
+
+    #[test]
+    fn test_block_cipher_fips_197() {
+        fips_197_appendix_c::<Aes128, Aes192, Aes256>();
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_cbc_256() {
+        let key: Vec<u8> = (0..32).collect();
+        let iv: Vec<u8> = vec![7; 16];
+        let input = b"Cooking MC's like a pound of bacon".to_vec();
+        let cipher = Aes256::new(&key).unwrap();
+
+        let encrypted = encrypt_cbc(&cipher, &input, &iv, true).unwrap();
+        assert_eq!(encrypted.len(), 48);
+        let decrypted = decrypt_cbc(&cipher, &encrypted, &iv).unwrap();
+
+        assert_eq!(input, pkcs_7_padding_validate(&decrypted, 16).unwrap());
+    }
+
+    #[test]
+    fn test_block_cipher_invalid_key_length() {
+        assert!(matches!(
+            Aes192::new(&[0; 16]),
+            Err(Error::InvalidKeyLength(16))
+        ));
+        assert!(matches!(
+            Aes256::new(&[0; 24]),
+            Err(Error::InvalidKeyLength(24))
+        ));
+    }
 }
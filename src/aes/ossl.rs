@@ -0,0 +1,57 @@
+use openssl::symm;
+
+use crate::aes::BlockCipher;
+use crate::error::{Error, Result};
+
+fn openssl_block(
+    cipher: symm::Cipher,
+    mode: symm::Mode,
+    key: &[u8],
+    block: &[u8],
+) -> Result<Vec<u8>> {
+    if block.len() != cipher.block_size() {
+        return Err(Error::InvalidDataLength(block.len()));
+    }
+
+    let mut c = symm::Crypter::new(cipher, mode, key, None)?;
+    c.pad(false);
+
+    let mut output = vec![0; block.len() + cipher.block_size()];
+    let count = c.update(block, &mut output)?;
+    let rest = c.finalize(&mut output[count..])?;
+    output.truncate(count + rest);
+
+    Ok(output)
+}
+
+macro_rules! openssl_aes {
+    ($name:ident, $key_size:expr, $cipher:expr) => {
+        pub struct $name {
+            key: Vec<u8>,
+        }
+
+        impl BlockCipher for $name {
+            const BLOCK_SIZE: usize = 16;
+            const KEY_SIZE: usize = $key_size;
+
+            fn new(key: &[u8]) -> Result<Self> {
+                if key.len() != Self::KEY_SIZE {
+                    return Err(Error::InvalidKeyLength(key.len()));
+                }
+                Ok($name { key: key.to_vec() })
+            }
+
+            fn encrypt_block(&self, block: &[u8]) -> Result<Vec<u8>> {
+                openssl_block($cipher, symm::Mode::Encrypt, &self.key, block)
+            }
+
+            fn decrypt_block(&self, block: &[u8]) -> Result<Vec<u8>> {
+                openssl_block($cipher, symm::Mode::Decrypt, &self.key, block)
+            }
+        }
+    };
+}
+
+openssl_aes!(Aes128, 16, symm::Cipher::aes_128_ecb());
+openssl_aes!(Aes192, 24, symm::Cipher::aes_192_ecb());
+openssl_aes!(Aes256, 32, symm::Cipher::aes_256_ecb());
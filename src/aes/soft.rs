@@ -0,0 +1,334 @@
+// Pure-Rust AES as described in FIPS-197.
+//
+// The state is kept as 16 bytes in column-major order, i.e. `state[r + 4 * c]`
+// is row `r` of column `c`, which is the order the bytes appear in the block.
+
+use crate::aes::BlockCipher;
+use crate::error::{Error, Result};
+
+const SBOX: [u8; 256] = [
+    0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab, 0x76,
+    0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf, 0x9c, 0xa4, 0x72, 0xc0,
+    0xb7, 0xfd, 0x93, 0x26, 0x36, 0x3f, 0xf7, 0xcc, 0x34, 0xa5, 0xe5, 0xf1, 0x71, 0xd8, 0x31, 0x15,
+    0x04, 0xc7, 0x23, 0xc3, 0x18, 0x96, 0x05, 0x9a, 0x07, 0x12, 0x80, 0xe2, 0xeb, 0x27, 0xb2, 0x75,
+    0x09, 0x83, 0x2c, 0x1a, 0x1b, 0x6e, 0x5a, 0xa0, 0x52, 0x3b, 0xd6, 0xb3, 0x29, 0xe3, 0x2f, 0x84,
+    0x53, 0xd1, 0x00, 0xed, 0x20, 0xfc, 0xb1, 0x5b, 0x6a, 0xcb, 0xbe, 0x39, 0x4a, 0x4c, 0x58, 0xcf,
+    0xd0, 0xef, 0xaa, 0xfb, 0x43, 0x4d, 0x33, 0x85, 0x45, 0xf9, 0x02, 0x7f, 0x50, 0x3c, 0x9f, 0xa8,
+    0x51, 0xa3, 0x40, 0x8f, 0x92, 0x9d, 0x38, 0xf5, 0xbc, 0xb6, 0xda, 0x21, 0x10, 0xff, 0xf3, 0xd2,
+    0xcd, 0x0c, 0x13, 0xec, 0x5f, 0x97, 0x44, 0x17, 0xc4, 0xa7, 0x7e, 0x3d, 0x64, 0x5d, 0x19, 0x73,
+    0x60, 0x81, 0x4f, 0xdc, 0x22, 0x2a, 0x90, 0x88, 0x46, 0xee, 0xb8, 0x14, 0xde, 0x5e, 0x0b, 0xdb,
+    0xe0, 0x32, 0x3a, 0x0a, 0x49, 0x06, 0x24, 0x5c, 0xc2, 0xd3, 0xac, 0x62, 0x91, 0x95, 0xe4, 0x79,
+    0xe7, 0xc8, 0x37, 0x6d, 0x8d, 0xd5, 0x4e, 0xa9, 0x6c, 0x56, 0xf4, 0xea, 0x65, 0x7a, 0xae, 0x08,
+    0xba, 0x78, 0x25, 0x2e, 0x1c, 0xa6, 0xb4, 0xc6, 0xe8, 0xdd, 0x74, 0x1f, 0x4b, 0xbd, 0x8b, 0x8a,
+    0x70, 0x3e, 0xb5, 0x66, 0x48, 0x03, 0xf6, 0x0e, 0x61, 0x35, 0x57, 0xb9, 0x86, 0xc1, 0x1d, 0x9e,
+    0xe1, 0xf8, 0x98, 0x11, 0x69, 0xd9, 0x8e, 0x94, 0x9b, 0x1e, 0x87, 0xe9, 0xce, 0x55, 0x28, 0xdf,
+    0x8c, 0xa1, 0x89, 0x0d, 0xbf, 0xe6, 0x42, 0x68, 0x41, 0x99, 0x2d, 0x0f, 0xb0, 0x54, 0xbb, 0x16,
+];
+
+const INV_SBOX: [u8; 256] = [
+    0x52, 0x09, 0x6a, 0xd5, 0x30, 0x36, 0xa5, 0x38, 0xbf, 0x40, 0xa3, 0x9e, 0x81, 0xf3, 0xd7, 0xfb,
+    0x7c, 0xe3, 0x39, 0x82, 0x9b, 0x2f, 0xff, 0x87, 0x34, 0x8e, 0x43, 0x44, 0xc4, 0xde, 0xe9, 0xcb,
+    0x54, 0x7b, 0x94, 0x32, 0xa6, 0xc2, 0x23, 0x3d, 0xee, 0x4c, 0x95, 0x0b, 0x42, 0xfa, 0xc3, 0x4e,
+    0x08, 0x2e, 0xa1, 0x66, 0x28, 0xd9, 0x24, 0xb2, 0x76, 0x5b, 0xa2, 0x49, 0x6d, 0x8b, 0xd1, 0x25,
+    0x72, 0xf8, 0xf6, 0x64, 0x86, 0x68, 0x98, 0x16, 0xd4, 0xa4, 0x5c, 0xcc, 0x5d, 0x65, 0xb6, 0x92,
+    0x6c, 0x70, 0x48, 0x50, 0xfd, 0xed, 0xb9, 0xda, 0x5e, 0x15, 0x46, 0x57, 0xa7, 0x8d, 0x9d, 0x84,
+    0x90, 0xd8, 0xab, 0x00, 0x8c, 0xbc, 0xd3, 0x0a, 0xf7, 0xe4, 0x58, 0x05, 0xb8, 0xb3, 0x45, 0x06,
+    0xd0, 0x2c, 0x1e, 0x8f, 0xca, 0x3f, 0x0f, 0x02, 0xc1, 0xaf, 0xbd, 0x03, 0x01, 0x13, 0x8a, 0x6b,
+    0x3a, 0x91, 0x11, 0x41, 0x4f, 0x67, 0xdc, 0xea, 0x97, 0xf2, 0xcf, 0xce, 0xf0, 0xb4, 0xe6, 0x73,
+    0x96, 0xac, 0x74, 0x22, 0xe7, 0xad, 0x35, 0x85, 0xe2, 0xf9, 0x37, 0xe8, 0x1c, 0x75, 0xdf, 0x6e,
+    0x47, 0xf1, 0x1a, 0x71, 0x1d, 0x29, 0xc5, 0x89, 0x6f, 0xb7, 0x62, 0x0e, 0xaa, 0x18, 0xbe, 0x1b,
+    0xfc, 0x56, 0x3e, 0x4b, 0xc6, 0xd2, 0x79, 0x20, 0x9a, 0xdb, 0xc0, 0xfe, 0x78, 0xcd, 0x5a, 0xf4,
+    0x1f, 0xdd, 0xa8, 0x33, 0x88, 0x07, 0xc7, 0x31, 0xb1, 0x12, 0x10, 0x59, 0x27, 0x80, 0xec, 0x5f,
+    0x60, 0x51, 0x7f, 0xa9, 0x19, 0xb5, 0x4a, 0x0d, 0x2d, 0xe5, 0x7a, 0x9f, 0x93, 0xc9, 0x9c, 0xef,
+    0xa0, 0xe0, 0x3b, 0x4d, 0xae, 0x2a, 0xf5, 0xb0, 0xc8, 0xeb, 0xbb, 0x3c, 0x83, 0x53, 0x99, 0x61,
+    0x17, 0x2b, 0x04, 0x7e, 0xba, 0x77, 0xd6, 0x26, 0xe1, 0x69, 0x14, 0x63, 0x55, 0x21, 0x0c, 0x7d,
+];
+
+const RCON: [u8; 10] = [0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0x1b, 0x36];
+
+fn xtime(x: u8) -> u8 {
+    (x << 1) ^ if x & 0x80 != 0 { 0x1b } else { 0 }
+}
+
+fn gmul(mut a: u8, mut b: u8) -> u8 {
+    let mut p = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            p ^= a;
+        }
+        a = xtime(a);
+        b >>= 1;
+    }
+    p
+}
+
+/// Expands a 16, 24 or 32 byte key into `rounds + 1` round keys.
+pub fn expand_key(key: &[u8]) -> Result<Vec<[u8; 16]>> {
+    let nk = key.len() / 4;
+    let rounds = match key.len() {
+        16 => 10,
+        24 => 12,
+        32 => 14,
+        len => return Err(Error::InvalidKeyLength(len)),
+    };
+
+    let total_words = 4 * (rounds + 1);
+    let mut words: Vec<[u8; 4]> = Vec::with_capacity(total_words);
+    for chunk in key.chunks(4) {
+        words.push([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+
+    for i in nk..total_words {
+        let mut temp = words[i - 1];
+        if i % nk == 0 {
+            // RotWord, SubWord and Rcon
+            temp = [
+                SBOX[temp[1] as usize] ^ RCON[i / nk - 1],
+                SBOX[temp[2] as usize],
+                SBOX[temp[3] as usize],
+                SBOX[temp[0] as usize],
+            ];
+        } else if nk > 6 && i % nk == 4 {
+            for b in temp.iter_mut() {
+                *b = SBOX[*b as usize];
+            }
+        }
+        let prev = words[i - nk];
+        words.push([
+            prev[0] ^ temp[0],
+            prev[1] ^ temp[1],
+            prev[2] ^ temp[2],
+            prev[3] ^ temp[3],
+        ]);
+    }
+
+    Ok(words
+        .chunks(4)
+        .map(|w| {
+            let mut round_key = [0; 16];
+            for (c, word) in w.iter().enumerate() {
+                round_key[4 * c..4 * c + 4].copy_from_slice(word);
+            }
+            round_key
+        })
+        .collect())
+}
+
+fn add_round_key(state: &mut [u8; 16], round_key: &[u8; 16]) {
+    for (s, k) in state.iter_mut().zip(round_key.iter()) {
+        *s ^= k;
+    }
+}
+
+fn sub_bytes(state: &mut [u8; 16]) {
+    for s in state.iter_mut() {
+        *s = SBOX[*s as usize];
+    }
+}
+
+fn inv_sub_bytes(state: &mut [u8; 16]) {
+    for s in state.iter_mut() {
+        *s = INV_SBOX[*s as usize];
+    }
+}
+
+fn shift_rows(state: &mut [u8; 16]) {
+    let old = *state;
+    for r in 1..4 {
+        for c in 0..4 {
+            state[r + 4 * c] = old[r + 4 * ((c + r) % 4)];
+        }
+    }
+}
+
+fn inv_shift_rows(state: &mut [u8; 16]) {
+    let old = *state;
+    for r in 1..4 {
+        for c in 0..4 {
+            state[r + 4 * ((c + r) % 4)] = old[r + 4 * c];
+        }
+    }
+}
+
+fn mix_columns(state: &mut [u8; 16]) {
+    for column in state.chunks_mut(4) {
+        let a = [column[0], column[1], column[2], column[3]];
+        column[0] = gmul(a[0], 2) ^ gmul(a[1], 3) ^ a[2] ^ a[3];
+        column[1] = a[0] ^ gmul(a[1], 2) ^ gmul(a[2], 3) ^ a[3];
+        column[2] = a[0] ^ a[1] ^ gmul(a[2], 2) ^ gmul(a[3], 3);
+        column[3] = gmul(a[0], 3) ^ a[1] ^ a[2] ^ gmul(a[3], 2);
+    }
+}
+
+fn inv_mix_columns(state: &mut [u8; 16]) {
+    for column in state.chunks_mut(4) {
+        let a = [column[0], column[1], column[2], column[3]];
+        column[0] = gmul(a[0], 14) ^ gmul(a[1], 11) ^ gmul(a[2], 13) ^ gmul(a[3], 9);
+        column[1] = gmul(a[0], 9) ^ gmul(a[1], 14) ^ gmul(a[2], 11) ^ gmul(a[3], 13);
+        column[2] = gmul(a[0], 13) ^ gmul(a[1], 9) ^ gmul(a[2], 14) ^ gmul(a[3], 11);
+        column[3] = gmul(a[0], 11) ^ gmul(a[1], 13) ^ gmul(a[2], 9) ^ gmul(a[3], 14);
+    }
+}
+
+fn to_state(block: &[u8]) -> Result<[u8; 16]> {
+    if block.len() != 16 {
+        return Err(Error::InvalidDataLength(block.len()));
+    }
+    let mut state = [0; 16];
+    state.copy_from_slice(block);
+    Ok(state)
+}
+
+/// AES with an already expanded key, for any of the three key sizes.
+pub struct Aes {
+    round_keys: Vec<[u8; 16]>,
+}
+
+impl Aes {
+    pub fn new(key: &[u8]) -> Result<Aes> {
+        Ok(Aes {
+            round_keys: expand_key(key)?,
+        })
+    }
+
+    pub fn rounds(&self) -> usize {
+        self.round_keys.len() - 1
+    }
+
+    pub fn encrypt_block(&self, block: &[u8]) -> Result<Vec<u8>> {
+        let mut state = to_state(block)?;
+        let rounds = self.rounds();
+
+        add_round_key(&mut state, &self.round_keys[0]);
+        for round_key in &self.round_keys[1..rounds] {
+            sub_bytes(&mut state);
+            shift_rows(&mut state);
+            mix_columns(&mut state);
+            add_round_key(&mut state, round_key);
+        }
+        sub_bytes(&mut state);
+        shift_rows(&mut state);
+        add_round_key(&mut state, &self.round_keys[rounds]);
+
+        Ok(state.to_vec())
+    }
+
+    pub fn decrypt_block(&self, block: &[u8]) -> Result<Vec<u8>> {
+        let mut state = to_state(block)?;
+        let rounds = self.rounds();
+
+        add_round_key(&mut state, &self.round_keys[rounds]);
+        for round_key in self.round_keys[1..rounds].iter().rev() {
+            inv_shift_rows(&mut state);
+            inv_sub_bytes(&mut state);
+            add_round_key(&mut state, round_key);
+            inv_mix_columns(&mut state);
+        }
+        inv_shift_rows(&mut state);
+        inv_sub_bytes(&mut state);
+        add_round_key(&mut state, &self.round_keys[0]);
+
+        Ok(state.to_vec())
+    }
+}
+
+macro_rules! soft_aes {
+    ($name:ident, $key_size:expr) => {
+        pub struct $name {
+            inner: Aes,
+        }
+
+        impl BlockCipher for $name {
+            const BLOCK_SIZE: usize = 16;
+            const KEY_SIZE: usize = $key_size;
+
+            fn new(key: &[u8]) -> Result<Self> {
+                if key.len() != Self::KEY_SIZE {
+                    return Err(Error::InvalidKeyLength(key.len()));
+                }
+                Ok($name {
+                    inner: Aes::new(key)?,
+                })
+            }
+
+            fn encrypt_block(&self, block: &[u8]) -> Result<Vec<u8>> {
+                self.inner.encrypt_block(block)
+            }
+
+            fn decrypt_block(&self, block: &[u8]) -> Result<Vec<u8>> {
+                self.inner.decrypt_block(block)
+            }
+        }
+    };
+}
+
+soft_aes!(Aes128, 16);
+soft_aes!(Aes192, 24);
+soft_aes!(Aes256, 32);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes::tests::fips_197_appendix_c;
+    use crate::utils::{decode_hex, encode_hex};
+
+    #[test]
+    fn test_fips_197_appendix_c() {
+        fips_197_appendix_c::<Aes128, Aes192, Aes256>();
+    }
+
+    #[test]
+    fn test_fips_197_appendix_b() {
+        let key = decode_hex("2b7e151628aed2a6abf7158809cf4f3c").unwrap();
+        let plaintext = decode_hex("3243f6a8885a308d313198a2e0370734").unwrap();
+        let cipher = Aes128::new(&key).unwrap();
+
+        assert_eq!(
+            "3925841d02dc09fbdc118597196a0b32",
+            encode_hex(&cipher.encrypt_block(&plaintext).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_expand_key_fips_197_appendix_a() {
+        let key = decode_hex("2b7e151628aed2a6abf7158809cf4f3c").unwrap();
+        let round_keys = expand_key(&key).unwrap();
+
+        assert_eq!(11, round_keys.len());
+        assert_eq!(
+            "a0fafe1788542cb123a339392a6c7605",
+            encode_hex(&round_keys[1])
+        );
+        assert_eq!(
+            "d014f9a8c9ee2589e13f0cc8b6630ca6",
+            encode_hex(&round_keys[10])
+        );
+    }
+
+    #[cfg(feature = "openssl")]
+    fn cross_check<C: BlockCipher, O: BlockCipher>() {
+        use crate::random;
+
+        for _ in 0..64 {
+            let key = random::random_key(C::KEY_SIZE);
+            let block = random::random_key(16);
+            let soft = C::new(&key).unwrap();
+            let ossl = O::new(&key).unwrap();
+
+            let encrypted = soft.encrypt_block(&block).unwrap();
+            assert_eq!(ossl.encrypt_block(&block).unwrap(), encrypted);
+            assert_eq!(ossl.decrypt_block(&encrypted).unwrap(), block);
+            assert_eq!(soft.decrypt_block(&encrypted).unwrap(), block);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "openssl")]
+    fn test_cross_check_openssl() {
+        use crate::aes::ossl;
+
+        cross_check::<Aes128, ossl::Aes128>();
+        cross_check::<Aes192, ossl::Aes192>();
+        cross_check::<Aes256, ossl::Aes256>();
+    }
+}
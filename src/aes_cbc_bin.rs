@@ -17,14 +17,14 @@ pub fn main() {
         process::exit(1);
     }
 
-    let ciphertext_b64 = load_file(&args[1]);
-    let ciphertext_bytes = decode_b64(&ciphertext_b64);
+    let ciphertext_b64 = load_file(&args[1]).expect("Unable to read file");
+    let ciphertext_bytes = decode_b64(&ciphertext_b64).expect("Invalid base64");
 
     let key = args[2].clone();
     let key_bytes = key.into_bytes();
     let iv: Vec<u8> = vec![0; 16];
 
-    let plaintext = decrypt_128_cbc(&key_bytes, &ciphertext_bytes, &iv);
+    let plaintext = decrypt_128_cbc(&key_bytes, &ciphertext_bytes, &iv).expect("Decryption failed");
 
     println!("Plaintext:\n{}", String::from_utf8(plaintext).unwrap());
 }
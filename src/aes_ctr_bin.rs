@@ -0,0 +1,33 @@
+use std::env;
+use std::process;
+
+use challenge::ctr::decrypt_128_ctr;
+use challenge::utils::decode_b64;
+
+fn usage() {
+    println!("Usage: aes_ctr <base64 encoded aes128 ctr ciphertext> <key> [nonce]");
+    std::process::exit(-1);
+}
+
+pub fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    if args.len() != 3 && args.len() != 4 {
+        usage();
+        process::exit(1);
+    }
+
+    let ciphertext_bytes = decode_b64(&args[1]).expect("Invalid base64");
+
+    let key = args[2].clone();
+    let key_bytes = key.into_bytes();
+    let nonce: u64 = match args.get(3) {
+        Some(nonce) => nonce.parse().expect("Invalid nonce"),
+        None => 0,
+    };
+
+    let plaintext =
+        decrypt_128_ctr(&key_bytes, nonce, &ciphertext_bytes).expect("Decryption failed");
+
+    println!("Plaintext:\n{}", String::from_utf8_lossy(&plaintext));
+}
@@ -17,13 +17,14 @@ pub fn main() {
         process::exit(1);
     }
 
-    let ciphertext_b64 = load_file(&args[1]);
-    let ciphertext_bytes = decode_b64(&ciphertext_b64);
+    let ciphertext_b64 = load_file(&args[1]).expect("Unable to read file");
+    let ciphertext_bytes = decode_b64(&ciphertext_b64).expect("Invalid base64");
 
     let key = args[2].clone();
     let key_bytes = key.into_bytes();
 
-    let plaintext = decrypt_128_ecb(&key_bytes, &ciphertext_bytes, false);
+    let plaintext =
+        decrypt_128_ecb(&key_bytes, &ciphertext_bytes, false).expect("Decryption failed");
 
     println!("Plaintext:\n{}", String::from_utf8(plaintext).unwrap());
 }
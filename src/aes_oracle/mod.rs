@@ -1,240 +1,411 @@
+use std::cell::Cell;
 use std::collections::HashMap;
 
 use crate::aes;
+use crate::error::{Error, Result};
 use crate::random;
 use crate::utils;
 
-#[derive(Debug)]
-pub enum CipherMode {
-    CBC,
-    ECB,
-}
-
-pub fn detection_oracle_random_method(ciphertext: &[u8]) -> CipherMode {
-    let (duplicates, chunks) = utils::count_duplicate_blocks(ciphertext, 16);
+pub const ROLLIN_SECRET: &str = "Um9sbGluJyBpbiBteSA1LjAKV2l0aCBteSByYWctdG9wIGRvd24gc28gbXkgaGFpciBjYW4gYmxvdwpUaGUgZ2lybGllcyBvbiBzdGFuZGJ5IHdhdmluZyBqdXN0IHRvIHNheSBoaQpEaWQgeW91IHN0b3A/IE5vLCBJIGp1c3QgZHJvdmUgYnkK";
 
-    println!("Duplicates: {:?}", duplicates);
-    println!("Chunks: {:?}", chunks);
+pub trait EncryptionOracle {
+    fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>>;
+}
 
-    if duplicates > 0 {
-        CipherMode::ECB
-    } else {
-        CipherMode::CBC
+impl<F> EncryptionOracle for F
+where
+    F: Fn(&[u8]) -> Result<Vec<u8>>,
+{
+    fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        self(data)
     }
 }
 
-pub fn detection_oracle_ecb_extra() {
-    let key = random::random_key(16);
-    // Detect block size
-    // - Encrypt A, AA, AAA until ciphertext length changes
-    //   block size is the difference in size.
-    let mut pad_len = 0;
-    let mut prev_size = 0;
-    let block_size: usize;
-    loop {
-        let pad = vec![65; pad_len];
-        let encrypted = encrypt_ecb_extra(&pad, &key);
-        let cur_size = encrypted.len();
-        if prev_size != 0 && prev_size != cur_size {
-            block_size = cur_size - prev_size;
-            break;
+// Wraps an oracle and counts how many times it has been queried.
+pub struct CountingOracle<'a, O: EncryptionOracle> {
+    oracle: &'a O,
+    queries: Cell<usize>,
+}
+
+impl<'a, O: EncryptionOracle> CountingOracle<'a, O> {
+    pub fn new(oracle: &'a O) -> CountingOracle<'a, O> {
+        CountingOracle {
+            oracle,
+            queries: Cell::new(0),
         }
-        prev_size = cur_size;
-        pad_len += 1;
     }
-    println!("Detected block_size={:?}", block_size);
-
-    // Detect ECB
-    // - 2x block size of A should encrypt to two equal blocks
-    let data = vec![65; block_size * 2];
-    let ciphertext = encrypt_ecb_extra(&data, &key);
 
-    if ciphertext[0..block_size] == ciphertext[block_size..block_size * 2] {
-        println!("Detected ECB");
-    } else {
-        println!("Detected non-ECB, bailing");
-        return;
+    pub fn queries(&self) -> usize {
+        self.queries.get()
     }
+}
 
-    let mut decrypted: Vec<u8> = Vec::with_capacity(block_size);
+impl<'a, O: EncryptionOracle> EncryptionOracle for CountingOracle<'a, O> {
+    fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        self.queries.set(self.queries.get() + 1);
+        self.oracle.encrypt(data)
+    }
+}
 
-    let minimal = vec![0; 0];
-    let encrypted = encrypt_ecb_extra(&minimal, &key);
-    let num_blocks = encrypted.len() / block_size;
+// AES-128-ECB(prefix || data || secret) under a fixed random key. The
+// prefix is empty unless created with `with_prefix` (challenge 12).
+pub struct EcbExtraOracle {
+    key: Vec<u8>,
+    prefix: Vec<u8>,
+    secret: Vec<u8>,
+}
 
-    let mut prev_decrypted_block: Vec<u8> = vec![65; block_size];
+impl EcbExtraOracle {
+    pub fn new(secret: &[u8]) -> EcbExtraOracle {
+        EcbExtraOracle::with_prefix(&[], secret)
+    }
 
-    for block_no in 0..num_blocks {
-        let mut decrypted_block: Vec<u8> = Vec::with_capacity(block_size);
+    pub fn with_prefix(prefix: &[u8], secret: &[u8]) -> EcbExtraOracle {
+        EcbExtraOracle {
+            key: random::random_key(16),
+            prefix: prefix.to_vec(),
+            secret: secret.to_vec(),
+        }
+    }
+}
 
-        for block_pos in 1..block_size + 1 {
-            let mut dict = HashMap::new();
+impl EncryptionOracle for EcbExtraOracle {
+    fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut plaintext = self.prefix.clone();
+        plaintext.extend_from_slice(data);
+        plaintext.extend_from_slice(&self.secret);
 
-            let mut data = vec![0; block_size];
-            for i in 0..block_size - decrypted_block.len() - 1 {
-                data[i] = prev_decrypted_block[decrypted_block.len() + i + 1];
-            }
+        aes::encrypt_128_ecb(&self.key, &plaintext, true)
+    }
+}
 
-            for i in 0..decrypted_block.len() {
-                data[block_size - decrypted_block.len() + i - 1] = decrypted_block[i];
-            }
+// AES-128-ECB(random-prefix || data || secret) under a fixed random key,
+// with a new random prefix for every query (challenge 14).
+pub struct EcbExtraRandomOracle {
+    key: Vec<u8>,
+    secret: Vec<u8>,
+}
 
-            for last_byte in 0..256 {
-                data[block_size - 1] = last_byte as u8;
-                let ciphertext = encrypt_ecb_extra(&data, &key);
+impl EcbExtraRandomOracle {
+    pub fn new(secret: &[u8]) -> EcbExtraRandomOracle {
+        EcbExtraRandomOracle {
+            key: random::random_key(16),
+            secret: secret.to_vec(),
+        }
+    }
+}
 
-                dict.insert(ciphertext[0..block_size].to_vec(), last_byte as u8);
-            }
+impl EncryptionOracle for EcbExtraRandomOracle {
+    fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut plaintext = random::random_prefix(8, 32);
+        plaintext.extend_from_slice(data);
+        plaintext.extend_from_slice(&self.secret);
 
-            let short = vec![65; block_size - block_pos];
-            let ciphertext = encrypt_ecb_extra(&short, &key);
+        aes::encrypt_128_ecb(&self.key, &plaintext, true)
+    }
+}
 
-            let block_start_pos = block_no * block_size;
-            let block_end_pos = (block_no + 1) * block_size;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CipherMode {
+    CBC,
+    ECB,
+}
 
-            if block_end_pos >= ciphertext.len() {
-                // We're now extracted all bytes
-                break;
-            }
+pub fn detection_oracle_random_method(ciphertext: &[u8]) -> CipherMode {
+    let (duplicates, _) = utils::count_duplicate_blocks(ciphertext, 16);
 
-            let block_to_lookup = &ciphertext[block_start_pos..block_end_pos].to_vec();
-            let found_block = dict.get(block_to_lookup);
-            if let Some(&next_byte) = found_block {
-                decrypted_block.push(next_byte);
-            } else {
-                panic!("Block not found, bug...");
-            }
-        }
-        prev_decrypted_block = decrypted_block.clone();
-        decrypted.extend(decrypted_block);
+    if duplicates > 0 {
+        CipherMode::ECB
+    } else {
+        CipherMode::CBC
     }
-    let extra_text = unsafe { String::from_utf8_unchecked(decrypted) };
-    println!("Extra text: {:?}", extra_text);
 }
 
-pub fn detection_oracle_ecb_extra_and_random() {
-    let key = random::random_key(16);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OracleProfile {
+    pub block_size: usize,
+    pub mode: CipherMode,
+    // Length of the fixed data the oracle puts before our input
+    pub prefix_len: usize,
+    // Length of the fixed data the oracle puts after our input
+    pub suffix_len: usize,
+}
 
-    // Detect block size
-    // - Encrypt A, AA, AAA until ciphertext length changes
-    //   block size is the difference in size.
-    let mut pad_len = 0;
-    let mut prev_size = 0;
-    let block_size: usize;
-    loop {
-        let pad = vec![65; pad_len];
-        let encrypted = encrypt_ecb_extra(&pad, &key);
-        let cur_size = encrypted.len();
-        if prev_size != 0 && prev_size != cur_size {
-            block_size = cur_size - prev_size;
-            break;
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// Ciphertext lengths seen before trusting the block size.
+const BLOCK_SIZE_SAMPLES: usize = 32;
+
+// Encrypt A, AA, AAA until ciphertext length changes. Every length is a
+// multiple of the block size and the first change adds exactly one block,
+// so the gcd of the lengths is the block size.
+//
+// With a random-length prefix the length jumps around by whole blocks, so
+// a few more lengths are taken in for the gcd to settle on the block size.
+pub fn detect_block_size<O: EncryptionOracle>(oracle: &O) -> Result<usize> {
+    let initial_size = oracle.encrypt(&[])?.len();
+    let mut block_size = initial_size;
+    let mut changed = false;
+
+    for pad_len in 1..=256 {
+        let cur_size = oracle.encrypt(&vec![b'A'; pad_len])?.len();
+        block_size = gcd(block_size, cur_size);
+        changed |= cur_size != initial_size;
+
+        if changed && pad_len >= BLOCK_SIZE_SAMPLES {
+            return Ok(block_size);
         }
-        prev_size = cur_size;
-        pad_len += 1;
     }
-    println!("Detected block_size={:?}", block_size);
 
-    // Detect ECB
-    // - A few block size times A should encrypt to a few equal blocks
-    let data = vec![65; block_size * 4];
-    let ciphertext = encrypt_ecb_extra_and_random(&data, &key);
+    Err(Error::AttackFailed(
+        "ciphertext length never changed".to_string(),
+    ))
+}
 
+// Three blocks of A always contain two aligned equal blocks, whatever the
+// prefix length, which only encrypt to equal blocks under ECB.
+pub fn detect_mode<O: EncryptionOracle>(oracle: &O, block_size: usize) -> Result<CipherMode> {
+    let ciphertext = oracle.encrypt(&vec![b'A'; block_size * 3])?;
     let (duplicates, _) = utils::count_duplicate_blocks(&ciphertext, block_size);
 
-    if duplicates > 1 {
-        println!("Detected ECB, {:?} duplicates", duplicates);
+    if duplicates > 0 {
+        Ok(CipherMode::ECB)
     } else {
-        println!("Detected non-ECB, bailing");
-        return;
+        Ok(CipherMode::CBC)
     }
+}
 
-    println!("Start extracting one byte at a time");
+fn first_differing_block(a: &[u8], b: &[u8], block_size: usize) -> Option<usize> {
+    a.chunks(block_size)
+        .zip(b.chunks(block_size))
+        .position(|(x, y)| x != y)
+}
 
-    let mut decrypted: Vec<u8> = Vec::with_capacity(block_size);
+// Works for any deterministic oracle, ECB or CBC with a fixed IV.
+//
+// Changing a single input byte first changes the block the prefix ends in.
+// Pushing that byte forward with filler, the block stops changing once the
+// filler completes it, which tells how far into the block the prefix ends.
+pub fn detect_prefix_length<O: EncryptionOracle>(oracle: &O, block_size: usize) -> Result<usize> {
+    let first =
+        first_differing_block(&oracle.encrypt(b"X")?, &oracle.encrypt(b"Y")?, block_size)
+            .ok_or_else(|| Error::AttackFailed("input does not affect ciphertext".to_string()))?;
+
+    for filler_len in 1..=block_size {
+        let mut x = vec![b'A'; filler_len];
+        let mut y = x.clone();
+        x.push(b'X');
+        y.push(b'Y');
+
+        let differing =
+            first_differing_block(&oracle.encrypt(&x)?, &oracle.encrypt(&y)?, block_size);
+        if differing != Some(first) {
+            return Ok((first + 1) * block_size - filler_len);
+        }
+    }
 
-    let minimal = vec![0; 0];
-    let encrypted = encrypt_ecb_extra_and_random(&minimal, &key);
-    let num_blocks = encrypted.len() / block_size;
+    Err(Error::AttackFailed(
+        "unable to detect prefix length".to_string(),
+    ))
+}
 
-    let mut prev_decrypted_block: Vec<u8> = vec![65; block_size];
+// Once input, prefix and suffix exactly fill the blocks the next byte adds a
+// whole block of padding, so the suffix is what is left of the initial size.
+pub fn detect_suffix_length<O: EncryptionOracle>(
+    oracle: &O,
+    block_size: usize,
+    prefix_len: usize,
+) -> Result<usize> {
+    let initial_size = oracle.encrypt(&[])?.len();
+    for pad_len in 1..=block_size {
+        let cur_size = oracle.encrypt(&vec![b'A'; pad_len])?.len();
+        if cur_size != initial_size {
+            return initial_size
+                .checked_sub(prefix_len + pad_len)
+                .ok_or_else(|| Error::AttackFailed("inconsistent prefix length".to_string()));
+        }
+    }
 
-    for block_no in 0..num_blocks {
-        println!("Working on block no {:?}", block_no);
+    Err(Error::AttackFailed(
+        "unable to detect suffix length".to_string(),
+    ))
+}
 
-        let mut decrypted_block: Vec<u8> = Vec::with_capacity(block_size);
+pub fn profile_oracle<O: EncryptionOracle>(oracle: &O) -> Result<OracleProfile> {
+    let block_size = detect_block_size(oracle)?;
+    let mode = detect_mode(oracle, block_size)?;
+    let prefix_len = detect_prefix_length(oracle, block_size)?;
+    let suffix_len = detect_suffix_length(oracle, block_size, prefix_len)?;
+
+    Ok(OracleProfile {
+        block_size,
+        mode,
+        prefix_len,
+        suffix_len,
+    })
+}
 
-        for block_pos in 1..block_size + 1 {
-            let mut dict = HashMap::new();
+// Recovers the secret appended by an ECB oracle one byte at a time. A fixed
+// prefix before the input is fine.
+//
+// Returns the secret and the number of oracle queries used.
+pub fn byte_at_a_time_ecb<O: EncryptionOracle>(oracle: &O) -> Result<(Vec<u8>, usize)> {
+    let oracle = CountingOracle::new(oracle);
 
-            let mut data = vec![0; block_size];
-            for i in 0..block_size - decrypted_block.len() - 1 {
-                data[i] = prev_decrypted_block[decrypted_block.len() + i + 1];
-            }
+    let profile = profile_oracle(&oracle)?;
+    if profile.mode != CipherMode::ECB {
+        return Err(Error::AttackFailed("oracle is not ECB".to_string()));
+    }
 
-            for i in 0..decrypted_block.len() {
-                data[block_size - decrypted_block.len() + i - 1] = decrypted_block[i];
-            }
+    let block_size = profile.block_size;
+    let secret_len = profile.suffix_len;
 
-            for last_byte in 0..256 {
-                data[block_size - 1] = last_byte as u8;
-                let ciphertext = encrypt_ecb_extra_and_random(&data, &key);
+    // Fill up the block the prefix ends in, and skip past it
+    let align = vec![b'B'; (block_size - profile.prefix_len % block_size) % block_size];
+    let skip = profile.prefix_len + align.len();
 
-                dict.insert(ciphertext[0..block_size].to_vec(), last_byte as u8);
-            }
+    let mut secret: Vec<u8> = Vec::with_capacity(secret_len);
 
-            let short = vec![65; block_size - block_pos];
-            let ciphertext = encrypt_ecb_extra_and_random(&short, &key);
+    for i in 0..secret_len {
+        // Pad so the next unknown byte is the last byte of a block
+        let pad = vec![b'A'; block_size - 1 - (i % block_size)];
+        let block_start = skip + (i / block_size) * block_size;
+        let ciphertext = oracle.encrypt(&[&align[..], &pad[..]].concat())?;
+        let target = &ciphertext[block_start..block_start + block_size];
 
-            let block_start_pos = block_no * block_size;
-            let block_end_pos = (block_no + 1) * block_size;
+        // The block_size - 1 bytes before the unknown byte are known
+        let mut probe: Vec<u8> = pad.iter().chain(secret.iter()).copied().collect();
+        probe.drain(..probe.len() - (block_size - 1));
+        probe.push(0);
 
-            if block_end_pos > ciphertext.len() {
-                // We're now extracted all bytes
-                break;
-            }
+        let mut dict = HashMap::new();
+        for last_byte in 0..=255 {
+            probe[block_size - 1] = last_byte;
+            let ciphertext = oracle.encrypt(&[&align[..], &probe[..]].concat())?;
+            dict.insert(ciphertext[skip..skip + block_size].to_vec(), last_byte);
+        }
 
-            let block_to_lookup = &ciphertext[block_start_pos..block_end_pos].to_vec();
-            let found_block = dict.get(block_to_lookup);
-            if let Some(&next_byte) = found_block {
-                decrypted_block.push(next_byte);
-            } else {
-                // panic!("Block not found, bug...");
-                continue;
+        match dict.get(target) {
+            Some(&next_byte) => secret.push(next_byte),
+            None => {
+                return Err(Error::AttackFailed(format!(
+                    "no dictionary match for byte {}",
+                    i
+                )))
             }
         }
-        prev_decrypted_block = decrypted_block.clone();
-        decrypted.extend(decrypted_block);
     }
-    let extra_text = unsafe { String::from_utf8_unchecked(decrypted) };
-    println!("Extra text: {:?}", extra_text);
+
+    Ok((secret, oracle.queries()))
 }
 
-pub fn encrypt_ecb_extra_and_random(data: &[u8], key: &[u8]) -> Vec<u8> {
-    let mut prefix = random::random_prefix(8, 32);
+// Number of times the sentinel run has to be seen before we trust it is the
+// encryption of the aligned sentinel block.
+const SENTINEL_CONFIRMATIONS: usize = 3;
+const MAX_ALIGNMENT_ATTEMPTS: usize = 4096;
+
+// Turns an oracle that prepends a random-length prefix into one without a
+// prefix.
+//
+// Every query is sent as sentinel || sentinel || data, where the sentinel is
+// a block of distinct bytes. Only when the prefix ends on a block boundary
+// does the ciphertext contain two consecutive encrypted sentinel blocks, and
+// everything after them is then the encryption of data || secret. Queries
+// are retried until that happens.
+pub struct AlignedOracle<'a, O: EncryptionOracle> {
+    oracle: &'a O,
+    block_size: usize,
+    sentinel: Vec<u8>,
+    encrypted_sentinel: Vec<u8>,
+}
 
-    let mut extra = utils::decode_b64("Um9sbGluJyBpbiBteSA1LjAKV2l0aCBteSByYWctdG9wIGRvd24gc28gbXkgaGFpciBjYW4gYmxvdwpUaGUgZ2lybGllcyBvbiBzdGFuZGJ5IHdhdmluZyBqdXN0IHRvIHNheSBoaQpEaWQgeW91IHN0b3A/IE5vLCBJIGp1c3QgZHJvdmUgYnkK");
+impl<'a, O: EncryptionOracle> AlignedOracle<'a, O> {
+    pub fn new(oracle: &'a O, block_size: usize) -> Result<AlignedOracle<'a, O>> {
+        let sentinel: Vec<u8> = (0..block_size).map(|i| 0x80 | i as u8).collect();
+        let double_sentinel = [&sentinel[..], &sentinel[..]].concat();
+
+        // A run of two equal blocks only shows up when the sentinel is
+        // aligned, so the most common run is the encrypted sentinel.
+        let mut runs: HashMap<Vec<u8>, usize> = HashMap::new();
+        for _ in 0..MAX_ALIGNMENT_ATTEMPTS {
+            let ciphertext = oracle.encrypt(&double_sentinel)?;
+            let blocks: Vec<&[u8]> = ciphertext.chunks(block_size).collect();
+
+            if let Some(pair) = blocks.windows(2).find(|pair| pair[0] == pair[1]) {
+                let count = runs.entry(pair[0].to_vec()).or_insert(0);
+                *count += 1;
+                if *count >= SENTINEL_CONFIRMATIONS {
+                    return Ok(AlignedOracle {
+                        oracle,
+                        block_size,
+                        sentinel,
+                        encrypted_sentinel: pair[0].to_vec(),
+                    });
+                }
+            }
+        }
 
-    let mut plaintext = data.to_owned();
+        Err(Error::AttackFailed(
+            "unable to find the encrypted sentinel block".to_string(),
+        ))
+    }
+}
 
-    plaintext.append(&mut extra);
-    prefix.append(&mut plaintext);
+impl<'a, O: EncryptionOracle> EncryptionOracle for AlignedOracle<'a, O> {
+    fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut input = self.sentinel.clone();
+        input.extend_from_slice(&self.sentinel);
+        input.extend_from_slice(data);
+
+        for _ in 0..MAX_ALIGNMENT_ATTEMPTS {
+            let ciphertext = self.oracle.encrypt(&input)?;
+            let blocks: Vec<&[u8]> = ciphertext.chunks(self.block_size).collect();
+
+            let aligned = blocks.windows(2).position(|pair| {
+                pair[0] == &self.encrypted_sentinel[..] && pair[1] == &self.encrypted_sentinel[..]
+            });
+            if let Some(index) = aligned {
+                return Ok(ciphertext[(index + 2) * self.block_size..].to_vec());
+            }
+        }
 
-    aes::encrypt_128_ecb(key, &prefix, true)
+        Err(Error::AttackFailed(
+            "prefix never aligned with the sentinel".to_string(),
+        ))
+    }
 }
 
-pub fn encrypt_ecb_extra(data: &[u8], key: &[u8]) -> Vec<u8> {
-    let mut extra = utils::decode_b64("Um9sbGluJyBpbiBteSA1LjAKV2l0aCBteSByYWctdG9wIGRvd24gc28gbXkgaGFpciBjYW4gYmxvdwpUaGUgZ2lybGllcyBvbiBzdGFuZGJ5IHdhdmluZyBqdXN0IHRvIHNheSBoaQpEaWQgeW91IHN0b3A/IE5vLCBJIGp1c3QgZHJvdmUgYnkK");
+// Recovers the secret from an ECB oracle that prepends a random-length
+// prefix on every query.
+//
+// Returns the secret and the number of queries made to the real oracle.
+pub fn byte_at_a_time_ecb_random_prefix<O: EncryptionOracle>(
+    oracle: &O,
+) -> Result<(Vec<u8>, usize)> {
+    let oracle = CountingOracle::new(oracle);
 
-    let mut plaintext = data.to_owned();
+    let block_size = detect_block_size(&oracle)?;
 
-    plaintext.append(&mut extra);
+    let aligned = AlignedOracle::new(&oracle, block_size)?;
+    let (secret, _) = byte_at_a_time_ecb(&aligned)?;
 
-    aes::encrypt_128_ecb(key, &plaintext, true)
+    Ok((secret, oracle.queries()))
 }
 
-pub fn encrypt_random_method(data: &[u8]) -> Vec<u8> {
+// encrypt_random_method adds between 5 and 9 random bytes on each side
+const RANDOM_METHOD_MIN_PADDING: usize = 5;
+const RANDOM_METHOD_MAX_PADDING: usize = 10;
+
+// Returns the ciphertext and the mode that was actually used, so guesses can
+// be checked.
+pub fn encrypt_random_method(data: &[u8]) -> Result<(Vec<u8>, CipherMode)> {
     let key = random::random_key(16);
-    assert!(key.len() == 16);
 
     let cipher_mode = if random::random_bool() {
         CipherMode::ECB
@@ -242,21 +413,226 @@ pub fn encrypt_random_method(data: &[u8]) -> Vec<u8> {
         CipherMode::CBC
     };
 
-    let mut prefix = random::random_prefix(5, 10);
-    let mut postfix = random::random_prefix(5, 10);
+    let mut prefix = random::random_prefix(RANDOM_METHOD_MIN_PADDING, RANDOM_METHOD_MAX_PADDING);
+    let mut postfix = random::random_prefix(RANDOM_METHOD_MIN_PADDING, RANDOM_METHOD_MAX_PADDING);
 
     let mut plaintext = data.to_owned();
 
     prefix.append(&mut plaintext);
     prefix.append(&mut postfix);
 
-    println!("Random method: {:?}", cipher_mode);
-
-    match cipher_mode {
-        CipherMode::ECB => aes::encrypt_128_ecb(&key, &prefix, true),
+    let ciphertext = match cipher_mode {
+        CipherMode::ECB => aes::encrypt_128_ecb(&key, &prefix, true)?,
         CipherMode::CBC => {
             let iv = random::random_key(16);
-            aes::encrypt_128_cbc(&key, &prefix, &iv, true)
+            aes::encrypt_128_cbc(&key, &prefix, &iv, true)?
         }
+    };
+
+    Ok((ciphertext, cipher_mode))
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ConfusionMatrix {
+    pub ecb_as_ecb: usize,
+    pub ecb_as_cbc: usize,
+    pub cbc_as_ecb: usize,
+    pub cbc_as_cbc: usize,
+}
+
+impl ConfusionMatrix {
+    pub fn record(&mut self, actual: CipherMode, guessed: CipherMode) {
+        match (actual, guessed) {
+            (CipherMode::ECB, CipherMode::ECB) => self.ecb_as_ecb += 1,
+            (CipherMode::ECB, CipherMode::CBC) => self.ecb_as_cbc += 1,
+            (CipherMode::CBC, CipherMode::ECB) => self.cbc_as_ecb += 1,
+            (CipherMode::CBC, CipherMode::CBC) => self.cbc_as_cbc += 1,
+        }
+    }
+
+    pub fn trials(&self) -> usize {
+        self.ecb_as_ecb + self.ecb_as_cbc + self.cbc_as_ecb + self.cbc_as_cbc
+    }
+
+    pub fn correct(&self) -> usize {
+        self.ecb_as_ecb + self.cbc_as_cbc
+    }
+
+    pub fn accuracy(&self) -> f64 {
+        if self.trials() == 0 {
+            return 0.0;
+        }
+        self.correct() as f64 / self.trials() as f64
+    }
+}
+
+// Runs encrypt_random_method on the chosen input `trials` times and tallies
+// the guesses of detection_oracle_random_method against the real mode.
+pub fn detection_accuracy(trials: usize, input: &[u8]) -> Result<ConfusionMatrix> {
+    let mut matrix = ConfusionMatrix::default();
+
+    for _ in 0..trials {
+        let (ciphertext, actual) = encrypt_random_method(input)?;
+        matrix.record(actual, detection_oracle_random_method(&ciphertext));
+    }
+
+    Ok(matrix)
+}
+
+// The shortest chosen plaintext that always gives two equal aligned blocks,
+// for a random prefix of min_prefix..max_prefix (exclusive) bytes: enough
+// filler to complete the prefix block in the worst case, plus two blocks.
+pub fn minimal_detection_input(block_size: usize, min_prefix: usize, max_prefix: usize) -> Vec<u8> {
+    let filler = (min_prefix..max_prefix)
+        .map(|prefix| (block_size - prefix % block_size) % block_size)
+        .max()
+        .unwrap_or(0);
+
+    vec![b'A'; filler + 2 * block_size]
+}
+
+pub fn minimal_random_method_input() -> Vec<u8> {
+    minimal_detection_input(16, RANDOM_METHOD_MIN_PADDING, RANDOM_METHOD_MAX_PADDING)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_byte_at_a_time_ecb_rollin() {
+        let secret = utils::decode_b64(ROLLIN_SECRET).unwrap();
+        let oracle = EcbExtraOracle::new(&secret);
+
+        let (recovered, queries) = byte_at_a_time_ecb(&oracle).unwrap();
+
+        assert_eq!(secret, recovered);
+        assert!(queries > 256 * secret.len());
+    }
+
+    #[test]
+    fn test_byte_at_a_time_ecb_closure_oracle() {
+        let key = random::random_key(16);
+        let secret = b"attack at dawn, bring snacks".to_vec();
+        let oracle = |data: &[u8]| {
+            let mut plaintext = data.to_vec();
+            plaintext.extend_from_slice(&secret);
+            aes::encrypt_128_ecb(&key, &plaintext, true)
+        };
+
+        let (recovered, _) = byte_at_a_time_ecb(&oracle).unwrap();
+
+        assert_eq!(secret, recovered);
+    }
+
+    #[test]
+    fn test_byte_at_a_time_ecb_fixed_prefix() {
+        let secret = utils::decode_b64(ROLLIN_SECRET).unwrap();
+
+        for prefix_len in [0, 1, 15, 16, 17, 37].iter() {
+            let prefix = random::random_key(*prefix_len);
+            let oracle = EcbExtraOracle::with_prefix(&prefix, &secret);
+
+            let (recovered, _) = byte_at_a_time_ecb(&oracle).unwrap();
+
+            assert_eq!(secret, recovered);
+        }
+    }
+
+    #[test]
+    fn test_profile_oracle_ecb() {
+        let oracle = EcbExtraOracle::with_prefix(b"0123456789", b"sixteen byte msg and then some");
+
+        let profile = profile_oracle(&oracle).unwrap();
+
+        assert_eq!(
+            OracleProfile {
+                block_size: 16,
+                mode: CipherMode::ECB,
+                prefix_len: 10,
+                suffix_len: 30,
+            },
+            profile
+        );
+    }
+
+    #[test]
+    fn test_profile_oracle_cbc() {
+        let key = random::random_key(16);
+        let iv = random::random_key(16);
+        let oracle = |data: &[u8]| {
+            let plaintext = [&b"comment1=cooking%20MCs;userdata="[..], data, b";x=y"].concat();
+            aes::encrypt_128_cbc(&key, &plaintext, &iv, true)
+        };
+
+        let profile = profile_oracle(&oracle).unwrap();
+
+        assert_eq!(
+            OracleProfile {
+                block_size: 16,
+                mode: CipherMode::CBC,
+                prefix_len: 32,
+                suffix_len: 4,
+            },
+            profile
+        );
+    }
+
+    #[test]
+    fn test_detect_block_size_random_prefix() {
+        let oracle = EcbExtraRandomOracle::new(b"secret");
+
+        assert_eq!(16, detect_block_size(&oracle).unwrap());
+    }
+
+    #[test]
+    fn test_byte_at_a_time_ecb_random_prefix_rollin() {
+        let secret = utils::decode_b64(ROLLIN_SECRET).unwrap();
+        let oracle = EcbExtraRandomOracle::new(&secret);
+
+        let (recovered, _) = byte_at_a_time_ecb_random_prefix(&oracle).unwrap();
+
+        assert_eq!(secret, recovered);
+    }
+
+    #[test]
+    fn test_minimal_detection_input() {
+        assert_eq!(43, minimal_random_method_input().len());
+        assert_eq!(32, minimal_detection_input(16, 0, 1).len());
+        assert_eq!(47, minimal_detection_input(16, 1, 17).len());
+    }
+
+    #[test]
+    fn test_detection_accuracy_minimal_input() {
+        let matrix = detection_accuracy(200, &minimal_random_method_input()).unwrap();
+
+        assert_eq!(200, matrix.trials());
+        assert_eq!(0, matrix.ecb_as_cbc + matrix.cbc_as_ecb);
+        assert_eq!(1.0, matrix.accuracy());
+    }
+
+    #[test]
+    fn test_detection_accuracy_short_input_misses_ecb() {
+        // One byte short of the minimal input, ECB with a 5 byte prefix is
+        // guessed as CBC
+        let mut input = minimal_random_method_input();
+        input.pop();
+
+        let matrix = detection_accuracy(400, &input).unwrap();
+
+        assert_eq!(0, matrix.cbc_as_ecb);
+        assert!(matrix.ecb_as_cbc > 0);
+    }
+
+    #[test]
+    fn test_byte_at_a_time_ecb_rejects_cbc() {
+        let key = random::random_key(16);
+        let iv = random::random_key(16);
+        let oracle = |data: &[u8]| aes::encrypt_128_cbc(&key, data, &iv, true);
+
+        assert!(matches!(
+            byte_at_a_time_ecb(&oracle),
+            Err(Error::AttackFailed(_))
+        ));
     }
 }
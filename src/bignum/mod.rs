@@ -0,0 +1,646 @@
+// Arbitrary-precision unsigned integers, enough for the public-key
+// challenges. Schoolbook multiplication and Knuth's algorithm D for
+// division, which is plenty fast for 1536-bit Diffie-Hellman.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Rem, Shl, Shr, Sub};
+
+use crate::error::{Error, Result};
+use crate::utils::{decode_hex, encode_hex};
+
+const LIMB_BITS: usize = 32;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub struct BigUint {
+    // Little-endian 32-bit limbs, without trailing zeros, so zero is empty
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    pub fn zero() -> BigUint {
+        BigUint { limbs: Vec::new() }
+    }
+
+    pub fn one() -> BigUint {
+        BigUint::from(1u32)
+    }
+
+    fn from_limbs(limbs: Vec<u32>) -> BigUint {
+        let mut n = BigUint { limbs };
+        n.normalize();
+        n
+    }
+
+    fn normalize(&mut self) {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+    }
+
+    pub fn from_bytes_be(bytes: &[u8]) -> BigUint {
+        let limbs = bytes
+            .rchunks(4)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .fold(0u32, |limb, &byte| (limb << 8) | byte as u32)
+            })
+            .collect();
+        BigUint::from_limbs(limbs)
+    }
+
+    // Big-endian bytes without leading zeros, but at least one byte.
+    pub fn to_bytes_be(&self) -> Vec<u8> {
+        let bytes: Vec<u8> = self
+            .limbs
+            .iter()
+            .rev()
+            .flat_map(|limb| limb.to_be_bytes())
+            .skip_while(|&byte| byte == 0)
+            .collect();
+
+        if bytes.is_empty() {
+            vec![0]
+        } else {
+            bytes
+        }
+    }
+
+    // Accepts an odd number of digits, as hex numbers are usually written.
+    pub fn from_hex(hex: &str) -> Result<BigUint> {
+        let bytes = if hex.len() % 2 == 1 {
+            decode_hex(&format!("0{}", hex))?
+        } else {
+            decode_hex(hex)?
+        };
+        Ok(BigUint::from_bytes_be(&bytes))
+    }
+
+    pub fn to_hex(&self) -> String {
+        encode_hex(&self.to_bytes_be())
+    }
+
+    pub fn to_u64(&self) -> Option<u64> {
+        match self.limbs.len() {
+            0 => Some(0),
+            1 => Some(self.limbs[0] as u64),
+            2 => Some(((self.limbs[1] as u64) << LIMB_BITS) | self.limbs[0] as u64),
+            _ => None,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    // The number of bits needed to write the number, 0 for zero.
+    pub fn bits(&self) -> usize {
+        match self.limbs.last() {
+            Some(top) => self.limbs.len() * LIMB_BITS - top.leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    pub fn bit(&self, i: usize) -> bool {
+        self.limbs
+            .get(i / LIMB_BITS)
+            .is_some_and(|limb| (limb >> (i % LIMB_BITS)) & 1 == 1)
+    }
+
+    pub fn checked_sub(&self, other: &BigUint) -> Option<BigUint> {
+        if *self < *other {
+            return None;
+        }
+
+        let mut limbs = Vec::with_capacity(self.limbs.len());
+        let mut borrow = 0u64;
+        for (i, &limb) in self.limbs.iter().enumerate() {
+            let sub = *other.limbs.get(i).unwrap_or(&0) as u64 + borrow;
+            let (diff, underflow) = (limb as u64).overflowing_sub(sub);
+            limbs.push(diff as u32);
+            borrow = underflow as u64;
+        }
+
+        Some(BigUint::from_limbs(limbs))
+    }
+
+    // Quotient and remainder.
+    pub fn div_rem(&self, divisor: &BigUint) -> Result<(BigUint, BigUint)> {
+        if divisor.is_zero() {
+            return Err(Error::DivisionByZero);
+        }
+        if *self < *divisor {
+            return Ok((BigUint::zero(), self.clone()));
+        }
+        if divisor.limbs.len() == 1 {
+            let (quotient, remainder) = self.div_rem_limb(divisor.limbs[0]);
+            return Ok((quotient, BigUint::from(remainder)));
+        }
+
+        Ok(self.div_rem_knuth(divisor))
+    }
+
+    fn div_rem_limb(&self, divisor: u32) -> (BigUint, u32) {
+        let mut quotient = vec![0; self.limbs.len()];
+        let mut remainder = 0u64;
+        for (i, &limb) in self.limbs.iter().enumerate().rev() {
+            let current = (remainder << LIMB_BITS) | limb as u64;
+            quotient[i] = (current / divisor as u64) as u32;
+            remainder = current % divisor as u64;
+        }
+
+        (BigUint::from_limbs(quotient), remainder as u32)
+    }
+
+    // Knuth, TAOCP vol. 2, 4.3.1, algorithm D. Needs a divisor of at least
+    // two limbs that is not larger than self.
+    fn div_rem_knuth(&self, divisor: &BigUint) -> (BigUint, BigUint) {
+        const BASE: u64 = 1 << LIMB_BITS;
+
+        // Shift so the top bit of the divisor is set, which keeps the
+        // quotient digit estimates off by at most two
+        let shift = divisor.limbs.last().unwrap().leading_zeros() as usize;
+        let v = (divisor << shift).limbs;
+        let mut u = (self << shift).limbs;
+        u.resize(self.limbs.len() + 1, 0);
+
+        let n = v.len();
+        let m = u.len() - n;
+        let mut quotient = vec![0; m];
+
+        for j in (0..m).rev() {
+            let top = ((u[j + n] as u64) << LIMB_BITS) | u[j + n - 1] as u64;
+            let mut qhat = top / v[n - 1] as u64;
+            let mut rhat = top % v[n - 1] as u64;
+            while qhat >= BASE
+                || qhat * v[n - 2] as u64 > ((rhat << LIMB_BITS) | u[j + n - 2] as u64)
+            {
+                qhat -= 1;
+                rhat += v[n - 1] as u64;
+                if rhat >= BASE {
+                    break;
+                }
+            }
+
+            // u[j..=j + n] -= qhat * v
+            let mut borrow = 0i64;
+            let mut carry = 0u64;
+            for i in 0..n {
+                let product = qhat * v[i] as u64 + carry;
+                carry = product >> LIMB_BITS;
+                let diff = u[i + j] as i64 - borrow - (product & (BASE - 1)) as i64;
+                u[i + j] = diff as u32;
+                borrow = (diff < 0) as i64;
+            }
+            let diff = u[j + n] as i64 - borrow - carry as i64;
+            u[j + n] = diff as u32;
+
+            // qhat was one too large, add v back
+            if diff < 0 {
+                qhat -= 1;
+                let mut carry = 0u64;
+                for i in 0..n {
+                    let sum = u[i + j] as u64 + v[i] as u64 + carry;
+                    u[i + j] = sum as u32;
+                    carry = sum >> LIMB_BITS;
+                }
+                u[j + n] = u[j + n].wrapping_add(carry as u32);
+            }
+
+            quotient[j] = qhat as u32;
+        }
+
+        u.truncate(n);
+        (
+            BigUint::from_limbs(quotient),
+            &BigUint::from_limbs(u) >> shift,
+        )
+    }
+
+    // self^exponent mod modulus, by square and multiply.
+    pub fn modpow(&self, exponent: &BigUint, modulus: &BigUint) -> Result<BigUint> {
+        if modulus.is_zero() {
+            return Err(Error::DivisionByZero);
+        }
+
+        let base = self.div_rem(modulus)?.1;
+        let mut result = BigUint::one().div_rem(modulus)?.1;
+        for i in (0..exponent.bits()).rev() {
+            result = (&result * &result).div_rem(modulus)?.1;
+            if exponent.bit(i) {
+                result = (&result * &base).div_rem(modulus)?.1;
+            }
+        }
+
+        Ok(result)
+    }
+
+    pub fn gcd(&self, other: &BigUint) -> BigUint {
+        let mut a = self.clone();
+        let mut b = other.clone();
+        while !b.is_zero() {
+            let r = &a % &b;
+            a = b;
+            b = r;
+        }
+        a
+    }
+
+    // The inverse of self mod modulus by the extended Euclidean algorithm,
+    // or None if they are not coprime. The Bezout coefficient is kept
+    // reduced mod modulus, so it never goes negative.
+    pub fn modinv(&self, modulus: &BigUint) -> Option<BigUint> {
+        if modulus.is_zero() {
+            return None;
+        }
+
+        let (mut old_r, mut r) = (self % modulus, modulus.clone());
+        let (mut old_s, mut s) = (BigUint::one() % modulus, BigUint::zero());
+        while !r.is_zero() {
+            let (q, rem) = old_r.div_rem(&r).ok()?;
+            old_r = std::mem::replace(&mut r, rem);
+
+            let qs = &(&q * &s) % modulus;
+            let new_s = &(&old_s + &(modulus - &qs)) % modulus;
+            old_s = std::mem::replace(&mut s, new_s);
+        }
+
+        if old_r == BigUint::one() {
+            Some(old_s)
+        } else {
+            None
+        }
+    }
+}
+
+impl From<u32> for BigUint {
+    fn from(n: u32) -> BigUint {
+        BigUint::from_limbs(vec![n])
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(n: u64) -> BigUint {
+        BigUint::from_limbs(vec![n as u32, (n >> LIMB_BITS) as u32])
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &BigUint) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &BigUint) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Decimal, by taking off nine digits at a time.
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+
+        let mut chunks = Vec::new();
+        let mut n = self.clone();
+        while !n.is_zero() {
+            let (quotient, chunk) = n.div_rem_limb(1_000_000_000);
+            chunks.push(chunk);
+            n = quotient;
+        }
+
+        let mut chunks = chunks.iter().rev();
+        write!(f, "{}", chunks.next().unwrap())?;
+        for chunk in chunks {
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Add<&'a BigUint> for &'a BigUint {
+    type Output = BigUint;
+
+    fn add(self, other: &BigUint) -> BigUint {
+        let (long, short) = if self.limbs.len() >= other.limbs.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+
+        let mut limbs = Vec::with_capacity(long.limbs.len() + 1);
+        let mut carry = 0u64;
+        for (i, &limb) in long.limbs.iter().enumerate() {
+            let sum = limb as u64 + *short.limbs.get(i).unwrap_or(&0) as u64 + carry;
+            limbs.push(sum as u32);
+            carry = sum >> LIMB_BITS;
+        }
+        limbs.push(carry as u32);
+
+        BigUint::from_limbs(limbs)
+    }
+}
+
+// Panics if other is larger, like the primitive types do.
+impl<'a> Sub<&'a BigUint> for &'a BigUint {
+    type Output = BigUint;
+
+    fn sub(self, other: &BigUint) -> BigUint {
+        self.checked_sub(other)
+            .expect("attempt to subtract with overflow")
+    }
+}
+
+impl<'a> Mul<&'a BigUint> for &'a BigUint {
+    type Output = BigUint;
+
+    fn mul(self, other: &BigUint) -> BigUint {
+        if self.is_zero() || other.is_zero() {
+            return BigUint::zero();
+        }
+
+        let mut limbs = vec![0u32; self.limbs.len() + other.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, &b) in other.limbs.iter().enumerate() {
+                let product = a as u64 * b as u64 + limbs[i + j] as u64 + carry;
+                limbs[i + j] = product as u32;
+                carry = product >> LIMB_BITS;
+            }
+            limbs[i + other.limbs.len()] = carry as u32;
+        }
+
+        BigUint::from_limbs(limbs)
+    }
+}
+
+// Division and remainder panic on a zero divisor, like the primitive types
+// do. Use div_rem to get an error instead.
+impl<'a> Div<&'a BigUint> for &'a BigUint {
+    type Output = BigUint;
+
+    fn div(self, other: &BigUint) -> BigUint {
+        self.div_rem(other).expect("attempt to divide by zero").0
+    }
+}
+
+impl<'a> Rem<&'a BigUint> for &'a BigUint {
+    type Output = BigUint;
+
+    fn rem(self, other: &BigUint) -> BigUint {
+        self.div_rem(other)
+            .expect("attempt to calculate the remainder with a divisor of zero")
+            .1
+    }
+}
+
+// The same operators on owned values.
+macro_rules! forward_binop {
+    ($trait:ident, $method:ident) => {
+        impl $trait<BigUint> for BigUint {
+            type Output = BigUint;
+
+            fn $method(self, other: BigUint) -> BigUint {
+                (&self).$method(&other)
+            }
+        }
+
+        impl<'a> $trait<&'a BigUint> for BigUint {
+            type Output = BigUint;
+
+            fn $method(self, other: &BigUint) -> BigUint {
+                (&self).$method(other)
+            }
+        }
+    };
+}
+
+forward_binop!(Add, add);
+forward_binop!(Sub, sub);
+forward_binop!(Mul, mul);
+forward_binop!(Div, div);
+forward_binop!(Rem, rem);
+
+impl Shl<usize> for &BigUint {
+    type Output = BigUint;
+
+    fn shl(self, shift: usize) -> BigUint {
+        if self.is_zero() {
+            return BigUint::zero();
+        }
+
+        let (limb_shift, bit_shift) = (shift / LIMB_BITS, shift % LIMB_BITS);
+        let mut limbs = vec![0; limb_shift];
+        let mut carry = 0u32;
+        for &limb in self.limbs.iter() {
+            limbs.push((limb << bit_shift) | carry);
+            carry = if bit_shift == 0 {
+                0
+            } else {
+                limb >> (LIMB_BITS - bit_shift)
+            };
+        }
+        limbs.push(carry);
+
+        BigUint::from_limbs(limbs)
+    }
+}
+
+impl Shr<usize> for &BigUint {
+    type Output = BigUint;
+
+    fn shr(self, shift: usize) -> BigUint {
+        let (limb_shift, bit_shift) = (shift / LIMB_BITS, shift % LIMB_BITS);
+        if limb_shift >= self.limbs.len() {
+            return BigUint::zero();
+        }
+
+        let high = &self.limbs[limb_shift..];
+        let limbs = high
+            .iter()
+            .enumerate()
+            .map(|(i, &limb)| {
+                let next = *high.get(i + 1).unwrap_or(&0);
+                if bit_shift == 0 {
+                    limb
+                } else {
+                    (limb >> bit_shift) | (next << (LIMB_BITS - bit_shift))
+                }
+            })
+            .collect();
+
+        BigUint::from_limbs(limbs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::{random_key, random_usize};
+
+    fn hex(s: &str) -> BigUint {
+        BigUint::from_hex(s).unwrap()
+    }
+
+    #[test]
+    fn test_bytes_and_hex() {
+        let n = BigUint::from_bytes_be(&[0, 0, 1, 2, 3, 4, 5]);
+        assert_eq!(vec![1, 2, 3, 4, 5], n.to_bytes_be());
+        assert_eq!("0102030405", n.to_hex());
+        assert_eq!(n, hex("102030405"));
+        assert_eq!(vec![0], BigUint::zero().to_bytes_be());
+        assert_eq!(BigUint::zero(), hex(""));
+        assert!(matches!(BigUint::from_hex("xyz"), Err(Error::InvalidHex)));
+    }
+
+    #[test]
+    fn test_bits() {
+        assert_eq!(0, BigUint::zero().bits());
+        assert_eq!(1, BigUint::one().bits());
+        assert_eq!(33, BigUint::from(1u64 << 32).bits());
+        assert!(BigUint::from(5u32).bit(2));
+        assert!(!BigUint::from(5u32).bit(1));
+        assert!(!BigUint::from(5u32).bit(100));
+    }
+
+    #[test]
+    fn test_ord() {
+        assert!(hex("100000000") > hex("ffffffff"));
+        assert!(hex("1ffffffff") < hex("200000000"));
+        assert_eq!(Ordering::Equal, hex("abc").cmp(&hex("0abc")));
+    }
+
+    #[test]
+    fn test_add_sub() {
+        let a = hex("ffffffffffffffffffffffff");
+        let b = BigUint::one();
+        assert_eq!(hex("1000000000000000000000000"), &a + &b);
+        assert_eq!(a, hex("1000000000000000000000000") - b);
+        assert_eq!(None, BigUint::one().checked_sub(&a));
+    }
+
+    #[test]
+    fn test_mul() {
+        let a = hex("ffffffffffffffffffffffff");
+        assert_eq!(
+            hex("fffffffffffffffffffffffe000000000000000000000001"),
+            &a * &a
+        );
+        assert_eq!(BigUint::zero(), a * BigUint::zero());
+    }
+
+    #[test]
+    fn test_div_rem() {
+        // Checked with python
+        let a = hex("d3c21bcecceda1000000123456789abcdef0fedcba9876543210");
+        let b = hex("fedcba98765432100123456789");
+        let (q, r) = a.div_rem(&b).unwrap();
+        assert_eq!(hex("d4b41e37b9458c01146dd49058"), q);
+        assert_eq!(hex("366f705b8a94f1341a39c78af8"), r);
+        assert_eq!(a, &q * &b + r);
+
+        let (q, r) = a.div_rem(&BigUint::from(10u32)).unwrap();
+        assert_eq!(
+            hex("152d02c7e14af680000001d208a5a912e318197c790f3f086b68"),
+            q
+        );
+        assert_eq!(BigUint::zero(), r);
+
+        assert!(matches!(
+            a.div_rem(&BigUint::zero()),
+            Err(Error::DivisionByZero)
+        ));
+    }
+
+    #[test]
+    fn test_div_rem_add_back() {
+        // The quotient digit estimate is still one too large after the
+        // correction loop here, so v has to be added back
+        let a = hex("12340000000000000007000000000000000000000000");
+        let b = hex("8000000000000000ffffffff");
+        let (q, r) = a.div_rem(&b).unwrap();
+        assert_eq!(hex("2467ffffffffffffb73e"), q);
+        assert_eq!(hex("2468000048c1ffffb73e"), r);
+    }
+
+    #[test]
+    fn test_div_rem_random() {
+        for _ in 0..200 {
+            let a = BigUint::from_bytes_be(&random_key(random_usize(1, 100)));
+            let b = BigUint::from_bytes_be(&random_key(random_usize(1, 50)));
+            if b.is_zero() {
+                continue;
+            }
+
+            let (q, r) = a.div_rem(&b).unwrap();
+            assert!(r < b);
+            assert_eq!(a, &q * &b + &r);
+        }
+    }
+
+    #[test]
+    fn test_shifts() {
+        let a = hex("123456789abcdef");
+        assert_eq!(hex("2468acf13579bde0000000000"), &a << 41);
+        assert_eq!(a, &(&a << 41) >> 41);
+        assert_eq!(hex("91a2b3c4"), &a >> 25);
+        assert_eq!(BigUint::zero(), &a >> 64);
+    }
+
+    #[test]
+    fn test_modpow() {
+        let base = hex("deadbeefcafebabe");
+        let exponent = hex("10001");
+        let modulus = hex("c5b3d7a1f3e29b8d7c6a5f4e3d2c1b0a9");
+        assert_eq!(
+            hex("9cab9e0bd84ed246d738c2f20a5b554b4"),
+            base.modpow(&exponent, &modulus).unwrap()
+        );
+
+        assert_eq!(
+            BigUint::from(6u32),
+            BigUint::from(5u32)
+                .modpow(&BigUint::from(117u32), &BigUint::from(37u32))
+                .unwrap()
+        );
+        assert_eq!(
+            BigUint::zero(),
+            base.modpow(&BigUint::zero(), &BigUint::one()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_gcd_modinv() {
+        assert_eq!(
+            BigUint::from(6u32),
+            BigUint::from(48u32).gcd(&BigUint::from(18u32))
+        );
+        assert_eq!(
+            Some(BigUint::from(2753u32)),
+            BigUint::from(17u32).modinv(&BigUint::from(3120u32))
+        );
+        assert_eq!(None, BigUint::from(6u32).modinv(&BigUint::from(9u32)));
+
+        let m = hex("c5b3d7a1f3e29b8d7c6a5f4e3d2c1b0a9");
+        let a = hex("deadbeefcafebabe");
+        let inverse = a.modinv(&m).unwrap();
+        assert_eq!(BigUint::one(), &(&a * &inverse) % &m);
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!("0", BigUint::zero().to_string());
+        assert_eq!(
+            "18446744073709551616",
+            (&BigUint::from(1u32) << 64).to_string()
+        );
+        assert_eq!("1000000000", BigUint::from(1_000_000_000u32).to_string());
+    }
+}
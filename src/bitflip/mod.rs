@@ -0,0 +1,302 @@
+use std::collections::HashMap;
+
+use crate::aes;
+use crate::ctr;
+use crate::error::{Error, Result};
+use crate::random;
+use crate::xor::xor;
+
+pub const USERDATA_PREFIX: &str = "comment1=cooking%20MCs;userdata=";
+pub const USERDATA_SUFFIX: &str = ";comment2=%20like%20a%20pound%20of%20bacon";
+
+// Quotes ; and = in the input and puts it between the prefix and suffix.
+pub fn wrap_userdata(input: &str) -> Vec<u8> {
+    let input = input.replace(";", "%3B").replace("=", "%3D");
+
+    format!("{}{}{}", USERDATA_PREFIX, input, USERDATA_SUFFIX).into_bytes()
+}
+
+pub fn check_admin(input: &str) -> bool {
+    let map: HashMap<_, _> = input.split(';').filter_map(|s| s.split_once('=')).collect();
+
+    matches!(map.get("admin"), Some(&"true"))
+}
+
+// The challenge 16 and 26 targets: encrypt wrapped userdata and tell
+// whether a ciphertext decrypts to an admin profile.
+pub trait UserdataService {
+    fn encrypt_userdata(&self, input: &str) -> Result<Vec<u8>>;
+
+    fn is_admin(&self, ciphertext: &[u8]) -> Result<bool>;
+}
+
+pub struct CbcUserdataService {
+    key: Vec<u8>,
+    iv: Vec<u8>,
+}
+
+impl Default for CbcUserdataService {
+    fn default() -> Self {
+        CbcUserdataService::new()
+    }
+}
+
+impl CbcUserdataService {
+    pub fn new() -> CbcUserdataService {
+        CbcUserdataService {
+            key: random::random_key(16),
+            iv: random::random_key(16),
+        }
+    }
+}
+
+impl UserdataService for CbcUserdataService {
+    fn encrypt_userdata(&self, input: &str) -> Result<Vec<u8>> {
+        aes::encrypt_128_cbc(&self.key, &wrap_userdata(input), &self.iv, true)
+    }
+
+    fn is_admin(&self, ciphertext: &[u8]) -> Result<bool> {
+        let plaintext = aes::decrypt_128_cbc(&self.key, ciphertext, &self.iv)?;
+        Ok(check_admin(&String::from_utf8_lossy(&plaintext)))
+    }
+}
+
+pub struct CtrUserdataService {
+    key: Vec<u8>,
+    nonce: u64,
+}
+
+impl Default for CtrUserdataService {
+    fn default() -> Self {
+        CtrUserdataService::new()
+    }
+}
+
+impl CtrUserdataService {
+    pub fn new() -> CtrUserdataService {
+        CtrUserdataService {
+            key: random::random_key(16),
+            nonce: random::random_usize(0, usize::MAX) as u64,
+        }
+    }
+}
+
+impl UserdataService for CtrUserdataService {
+    fn encrypt_userdata(&self, input: &str) -> Result<Vec<u8>> {
+        ctr::encrypt_128_ctr(&self.key, self.nonce, &wrap_userdata(input))
+    }
+
+    fn is_admin(&self, ciphertext: &[u8]) -> Result<bool> {
+        let plaintext = ctr::decrypt_128_ctr(&self.key, self.nonce, ciphertext)?;
+        Ok(check_admin(&String::from_utf8_lossy(&plaintext)))
+    }
+}
+
+// The challenge 27 target. Uses the key as the IV, and complains about high
+// ascii in the plaintext with an error that includes the plaintext.
+pub struct KeyAsIvService {
+    key: Vec<u8>,
+}
+
+impl Default for KeyAsIvService {
+    fn default() -> Self {
+        KeyAsIvService::new()
+    }
+}
+
+impl KeyAsIvService {
+    pub fn new() -> KeyAsIvService {
+        KeyAsIvService {
+            key: random::random_key(16),
+        }
+    }
+}
+
+impl UserdataService for KeyAsIvService {
+    fn encrypt_userdata(&self, input: &str) -> Result<Vec<u8>> {
+        aes::encrypt_128_cbc(&self.key, &wrap_userdata(input), &self.key, true)
+    }
+
+    fn is_admin(&self, ciphertext: &[u8]) -> Result<bool> {
+        let plaintext = aes::decrypt_128_cbc(&self.key, ciphertext, &self.key)?;
+        if !plaintext.is_ascii() {
+            return Err(Error::InvalidAscii(plaintext));
+        }
+        Ok(check_admin(&String::from_utf8_lossy(&plaintext)))
+    }
+}
+
+// Sends C1, 0, C1 to a service using the key as IV. The third block then
+// decrypts to P1 ^ key, while the first is still P1, so the plaintext leaked
+// in the error gives the key. The zero block makes the error very likely.
+pub fn recover_key_as_iv<S: UserdataService>(service: &S) -> Result<Vec<u8>> {
+    let block_size = 16;
+    let ciphertext = service.encrypt_userdata("")?;
+    if ciphertext.len() < block_size {
+        return Err(Error::InvalidDataLength(ciphertext.len()));
+    }
+
+    let first = &ciphertext[..block_size];
+    let mut forged = first.to_vec();
+    forged.extend_from_slice(&vec![0; block_size]);
+    forged.extend_from_slice(first);
+
+    match service.is_admin(&forged) {
+        Err(Error::InvalidAscii(plaintext)) => Ok(xor(
+            &plaintext[..block_size],
+            &plaintext[2 * block_size..3 * block_size],
+        )),
+        Err(err) => Err(err),
+        Ok(_) => Err(Error::AttackFailed(
+            "forged ciphertext decrypted to ascii".to_string(),
+        )),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlipMode {
+    // Flipping a ciphertext bit flips the same bit in the next block of
+    // plaintext, and garbles the block the bit is in.
+    Cbc(usize),
+    // Flipping a ciphertext bit flips the same plaintext bit.
+    Ctr,
+}
+
+// Filler for the userdata, flipped into the injection afterwards.
+const FILLER: u8 = b'A';
+
+// Makes the service encrypt userdata of filler bytes and flips the
+// ciphertext so the filler decrypts to `injection`. Only the length of the
+// prefix before the userdata needs to be known.
+//
+// In CBC mode the injection goes into the block after a block of filler,
+// which is the one that gets garbled, so it has to fit in a block.
+pub fn bitflip_attack<S: UserdataService>(
+    service: &S,
+    mode: FlipMode,
+    prefix_len: usize,
+    injection: &[u8],
+) -> Result<Vec<u8>> {
+    let (filler_len, injection_offset, flip_offset) = match mode {
+        FlipMode::Cbc(block_size) => {
+            if injection.len() > block_size {
+                return Err(Error::AttackFailed(format!(
+                    "injection of {} bytes does not fit in a block",
+                    injection.len()
+                )));
+            }
+            // Fill up the block the prefix ends in, then one whole block
+            // to garble
+            let align = (block_size - prefix_len % block_size) % block_size;
+            let offset = prefix_len + align + block_size;
+            (
+                align + block_size + injection.len(),
+                offset,
+                offset - block_size,
+            )
+        }
+        FlipMode::Ctr => (injection.len(), prefix_len, prefix_len),
+    };
+
+    let filler = String::from_utf8(vec![FILLER; filler_len]).expect("Filler is ascii");
+    let mut ciphertext = service.encrypt_userdata(&filler)?;
+
+    if injection_offset + injection.len() > ciphertext.len() {
+        return Err(Error::AttackFailed(
+            "ciphertext shorter than the known prefix".to_string(),
+        ));
+    }
+
+    for (i, &byte) in injection.iter().enumerate() {
+        ciphertext[flip_offset + i] ^= FILLER ^ byte;
+    }
+
+    Ok(ciphertext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_admin_1() {
+        assert!(check_admin("asdf=1;admin=true;qwerty=2"));
+    }
+
+    #[test]
+    fn test_check_admin_2() {
+        assert!(!check_admin("asdf=1;admin=false;qwerty=2"));
+    }
+
+    #[test]
+    fn test_check_admin_3() {
+        assert!(!check_admin("asdf=1;qwerty=2"));
+    }
+
+    #[test]
+    fn test_userdata_is_quoted() {
+        let service = CbcUserdataService::new();
+        let ciphertext = service.encrypt_userdata(";admin=true").unwrap();
+
+        assert!(!service.is_admin(&ciphertext).unwrap());
+        assert_eq!(
+            b"comment1=cooking%20MCs;userdata=%3Badmin%3Dtrue;comment2=%20like%20a%20pound%20of%20bacon"
+                .to_vec(),
+            wrap_userdata(";admin=true")
+        );
+    }
+
+    #[test]
+    fn test_cbc_bitflip_attack() {
+        let service = CbcUserdataService::new();
+        let ciphertext = bitflip_attack(
+            &service,
+            FlipMode::Cbc(16),
+            USERDATA_PREFIX.len(),
+            b";admin=true;",
+        )
+        .unwrap();
+
+        assert!(service.is_admin(&ciphertext).unwrap());
+    }
+
+    #[test]
+    fn test_cbc_bitflip_attack_injection_too_long() {
+        let service = CbcUserdataService::new();
+
+        assert!(matches!(
+            bitflip_attack(
+                &service,
+                FlipMode::Cbc(16),
+                USERDATA_PREFIX.len(),
+                b";admin=true;aaaaa"
+            ),
+            Err(Error::AttackFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_ctr_bitflip_attack() {
+        let service = CtrUserdataService::new();
+        let ciphertext = bitflip_attack(
+            &service,
+            FlipMode::Ctr,
+            USERDATA_PREFIX.len(),
+            b";admin=true;",
+        )
+        .unwrap();
+
+        assert!(service.is_admin(&ciphertext).unwrap());
+    }
+
+    #[test]
+    fn test_recover_key_as_iv() {
+        let service = KeyAsIvService::new();
+
+        let ciphertext = service.encrypt_userdata("hello").unwrap();
+        assert!(!service.is_admin(&ciphertext).unwrap());
+
+        let key = recover_key_as_iv(&service).unwrap();
+        assert_eq!(service.key, key);
+    }
+}
@@ -1,6 +1,7 @@
 use std::env;
 use std::process;
 
+use challenge::scorer::EnglishScorer;
 use challenge::utils::{decode_b64, load_file};
 use challenge::xor::{break_repeating_key, hamming_distance, repeating_key, xor};
 
@@ -56,15 +57,15 @@ pub fn main() {
         process::exit(1);
     }
 
-    let ciphertext_b64 = load_file(&args[1]);
+    let ciphertext_b64 = load_file(&args[1]).expect("Unable to read file");
 
-    let ciphertext_bytes = decode_b64(&ciphertext_b64);
+    let ciphertext_bytes = decode_b64(&ciphertext_b64).expect("Invalid base64");
     println!("Input bytes: {:?}", ciphertext_bytes.len());
 
     let key_size = find_key_size(&ciphertext_bytes);
     println!("Min key size: {:?}", key_size);
 
-    let key = break_repeating_key(key_size, &ciphertext_bytes);
+    let key = break_repeating_key(key_size, &ciphertext_bytes, &EnglishScorer::default());
     println!("Key: {:?}", String::from_utf8(key.clone()).unwrap());
 
     let full_key = repeating_key(&key, ciphertext_bytes.len());
@@ -1,4 +1,5 @@
 use crate::aes;
+use crate::error::{Error, Result};
 
 #[derive(Debug)]
 pub struct KV {
@@ -6,21 +7,29 @@ pub struct KV {
     value: String,
 }
 
-pub fn parse_querystring(querystring: &str) -> Vec<KV> {
-    let parts: Vec<&str> = querystring.split('&').collect();
-
-    parts
-        .into_iter()
-        .map(|part| {
-            let part_list: Vec<&str> = part.split('=').collect();
-            KV {
-                key: part_list[0].to_string(),
-                value: part_list[1].to_string(),
-            }
+pub fn parse_querystring(querystring: &str) -> Result<Vec<KV>> {
+    querystring
+        .split('&')
+        .map(|part| match part.split_once('=') {
+            Some((key, value)) if !key.is_empty() && !value.contains('=') => Ok(KV {
+                key: key.to_string(),
+                value: value.to_string(),
+            }),
+            _ => Err(Error::MalformedQueryString(part.to_string())),
         })
         .collect()
 }
 
+// The value of the last item with the key, so later items override earlier
+// ones.
+pub fn lookup<'a>(items: &'a [KV], key: &str) -> Option<&'a str> {
+    items
+        .iter()
+        .rev()
+        .find(|item| item.key == key)
+        .map(|item| item.value.as_str())
+}
+
 pub fn encode_querystring(items: Vec<KV>) -> String {
     items
         .into_iter()
@@ -49,7 +58,7 @@ pub fn profile_for(email: &str) -> String {
     encode_querystring(items)
 }
 
-pub fn encrypt_cookie(email: &str, key: &[u8]) -> Vec<u8> {
+pub fn encrypt_cookie(email: &str, key: &[u8]) -> Result<Vec<u8>> {
     let profile = profile_for(email);
     println!("Profile: {:?}", profile);
     let profile_bytes = profile.into_bytes();
@@ -57,8 +66,36 @@ pub fn encrypt_cookie(email: &str, key: &[u8]) -> Vec<u8> {
     aes::encrypt_128_ecb(key, &profile_bytes, true)
 }
 
-pub fn decrypt_cookie(cookie: Vec<u8>, key: &[u8]) -> Vec<KV> {
-    let encoded = aes::decrypt_128_ecb(key, &cookie, true);
-    let querystring = String::from_utf8(encoded).expect("Invalid string");
+pub fn decrypt_cookie(cookie: Vec<u8>, key: &[u8]) -> Result<Vec<KV>> {
+    let encoded = aes::decrypt_128_ecb(key, &cookie, true)?;
+    let querystring = String::from_utf8(encoded).map_err(|_| Error::InvalidUtf8)?;
     parse_querystring(&querystring)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_querystring() {
+        let items = parse_querystring("email=foo@bar.com&uid=10&role=user").unwrap();
+        assert_eq!(3, items.len());
+        assert_eq!("role", items[2].key);
+        assert_eq!("user", items[2].value);
+    }
+
+    #[test]
+    fn test_lookup() {
+        let items = parse_querystring("email=foo@bar.com&role=user&role=admin").unwrap();
+        assert_eq!(Some("foo@bar.com"), lookup(&items, "email"));
+        assert_eq!(Some("admin"), lookup(&items, "role"));
+        assert_eq!(None, lookup(&items, "uid"));
+    }
+
+    #[test]
+    fn test_parse_querystring_malformed() {
+        assert!(parse_querystring("email=foo@bar.com&uid").is_err());
+        assert!(parse_querystring("=10").is_err());
+        assert!(parse_querystring("role=user=admin").is_err());
+    }
+}
@@ -0,0 +1,466 @@
+use crate::aes::{Aes128, BlockCipher};
+use crate::error::{Error, Result};
+use crate::random::random_key;
+use crate::scorer::Scorer;
+use crate::xor::{break_repeating_key, search_single_char_key, xor};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CounterLayout {
+    // 64 bit nonce followed by a 64 bit block counter, both little-endian.
+    // This is the layout cryptopals uses.
+    LittleEndian64,
+    // 96 bit nonce followed by a 32 bit big-endian block counter, as in
+    // NIST SP 800-38A and GCM.
+    BigEndian96,
+}
+
+impl CounterLayout {
+    pub fn nonce_size(self) -> usize {
+        match self {
+            CounterLayout::LittleEndian64 => 8,
+            CounterLayout::BigEndian96 => 12,
+        }
+    }
+
+    fn max_counter(self) -> u64 {
+        match self {
+            CounterLayout::LittleEndian64 => u64::MAX,
+            CounterLayout::BigEndian96 => u32::MAX as u64,
+        }
+    }
+}
+
+pub struct Ctr<C: BlockCipher> {
+    cipher: C,
+    nonce: Vec<u8>,
+    layout: CounterLayout,
+    initial_counter: u64,
+    position: u64,
+}
+
+impl<C: BlockCipher> Ctr<C> {
+    pub fn new(cipher: C, nonce: &[u8], layout: CounterLayout) -> Result<Ctr<C>> {
+        if C::BLOCK_SIZE != 16 {
+            return Err(Error::InvalidDataLength(C::BLOCK_SIZE));
+        }
+        if nonce.len() != layout.nonce_size() {
+            return Err(Error::InvalidNonceLength(nonce.len()));
+        }
+
+        Ok(Ctr {
+            cipher,
+            nonce: nonce.to_vec(),
+            layout,
+            initial_counter: 0,
+            position: 0,
+        })
+    }
+
+    pub fn with_initial_counter(mut self, initial_counter: u64) -> Ctr<C> {
+        self.initial_counter = initial_counter;
+        self
+    }
+
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    pub fn seek(&mut self, position: u64) {
+        self.position = position;
+    }
+
+    pub fn counter_block(&self, block_index: u64) -> Result<Vec<u8>> {
+        let counter = self
+            .initial_counter
+            .checked_add(block_index)
+            .filter(|&c| c <= self.layout.max_counter())
+            .ok_or(Error::CounterOverflow)?;
+
+        let mut block = self.nonce.clone();
+        match self.layout {
+            CounterLayout::LittleEndian64 => block.extend_from_slice(&counter.to_le_bytes()),
+            CounterLayout::BigEndian96 => block.extend_from_slice(&(counter as u32).to_be_bytes()),
+        }
+
+        Ok(block)
+    }
+
+    // Keystream bytes [offset, offset + len), only generating the blocks
+    // that are needed.
+    pub fn keystream(&self, offset: u64, len: usize) -> Result<Vec<u8>> {
+        let block_size = C::BLOCK_SIZE as u64;
+        let end = offset
+            .checked_add(len as u64)
+            .ok_or(Error::CounterOverflow)?;
+        // Rounded up without overflowing near u64::MAX
+        let end_block = end / block_size + (end % block_size != 0) as u64;
+
+        let mut keystream = Vec::with_capacity(len + C::BLOCK_SIZE);
+        for block_index in offset / block_size..end_block {
+            let counter_block = self.counter_block(block_index)?;
+            keystream.extend_from_slice(&self.cipher.encrypt_block(&counter_block)?);
+        }
+
+        let skip = (offset % block_size) as usize;
+        Ok(keystream[skip..skip + len].to_vec())
+    }
+
+    pub fn apply_keystream_at(&self, offset: u64, data: &[u8]) -> Result<Vec<u8>> {
+        let keystream = self.keystream(offset, data.len())?;
+        Ok(xor(data, &keystream))
+    }
+
+    pub fn apply_keystream(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        let output = self.apply_keystream_at(self.position, data)?;
+        self.position += data.len() as u64;
+        Ok(output)
+    }
+}
+
+pub fn encrypt_128_ctr(key: &[u8], nonce: u64, data: &[u8]) -> Result<Vec<u8>> {
+    let ctr = Ctr::new(
+        Aes128::new(key)?,
+        &nonce.to_le_bytes(),
+        CounterLayout::LittleEndian64,
+    )?;
+    ctr.apply_keystream_at(0, data)
+}
+
+pub fn decrypt_128_ctr(key: &[u8], nonce: u64, data: &[u8]) -> Result<Vec<u8>> {
+    encrypt_128_ctr(key, nonce, data)
+}
+
+// Replaces the plaintext at `offset` with `newtext`, re-encrypting only
+// those bytes under the same key and the nonce encrypt_128_ctr uses by
+// default (0). The ciphertext grows if `newtext` runs past its end.
+pub fn edit(ciphertext: &[u8], key: &[u8], offset: usize, newtext: &[u8]) -> Result<Vec<u8>> {
+    if offset > ciphertext.len() {
+        return Err(Error::InvalidOffset(offset));
+    }
+
+    let ctr = Ctr::new(
+        Aes128::new(key)?,
+        &0u64.to_le_bytes(),
+        CounterLayout::LittleEndian64,
+    )?;
+    let encrypted = ctr.apply_keystream_at(offset as u64, newtext)?;
+
+    let mut output = ciphertext[..offset].to_vec();
+    output.extend_from_slice(&encrypted);
+    if offset + newtext.len() < ciphertext.len() {
+        output.extend_from_slice(&ciphertext[offset + newtext.len()..]);
+    }
+
+    Ok(output)
+}
+
+// The challenge 25 target, exposing edit but keeping the key to itself.
+pub struct CtrEditOracle {
+    key: Vec<u8>,
+}
+
+impl Default for CtrEditOracle {
+    fn default() -> Self {
+        CtrEditOracle::new()
+    }
+}
+
+impl CtrEditOracle {
+    pub fn new() -> CtrEditOracle {
+        CtrEditOracle {
+            key: random_key(16),
+        }
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        encrypt_128_ctr(&self.key, 0, plaintext)
+    }
+
+    pub fn edit(&self, ciphertext: &[u8], offset: usize, newtext: &[u8]) -> Result<Vec<u8>> {
+        edit(ciphertext, &self.key, offset, newtext)
+    }
+}
+
+// Overwriting the whole plaintext with zeros makes the oracle hand back the
+// keystream itself.
+pub fn recover_plaintext_with_edit<F>(edit: F, ciphertext: &[u8]) -> Result<Vec<u8>>
+where
+    F: Fn(&[u8], usize, &[u8]) -> Result<Vec<u8>>,
+{
+    let keystream = edit(ciphertext, 0, &vec![0; ciphertext.len()])?;
+    Ok(xor(ciphertext, &keystream))
+}
+
+// Reusing a nonce makes every ciphertext the xor of its plaintext with the
+// same keystream. Truncated to the shortest ciphertext and concatenated, this
+// is repeating key xor with the key size being that length.
+pub fn break_fixed_nonce_truncated<S: Scorer>(ciphertexts: &[Vec<u8>], scorer: &S) -> Vec<u8> {
+    let min_len = match ciphertexts.iter().map(|c| c.len()).min() {
+        Some(len) if len > 0 => len,
+        _ => return Vec::new(),
+    };
+
+    let concatenated: Vec<u8> = ciphertexts
+        .iter()
+        .flat_map(|c| c[..min_len].iter().copied())
+        .collect();
+
+    break_repeating_key(min_len, &concatenated, scorer)
+}
+
+// As above, but without truncating. Column i is made up of byte i of every
+// ciphertext long enough to have one, so the keystream is recovered up to
+// the longest ciphertext. The last columns only have a few bytes to score
+// and are less reliable, and a column of only letters (like the first one,
+// if every line is capitalized) scores the same in upper and lower case.
+pub fn break_fixed_nonce<S: Scorer>(ciphertexts: &[Vec<u8>], scorer: &S) -> Vec<u8> {
+    let max_len = ciphertexts.iter().map(|c| c.len()).max().unwrap_or(0);
+
+    (0..max_len)
+        .map(|i| {
+            let column: Vec<u8> = ciphertexts
+                .iter()
+                .filter_map(|c| c.get(i))
+                .copied()
+                .collect();
+            search_single_char_key(&column, scorer)[0].key
+        })
+        .collect()
+}
+
+// Decrypts every ciphertext with the recovered keystream. Ciphertexts longer
+// than the keystream are cut to its length.
+pub fn decrypt_fixed_nonce(ciphertexts: &[Vec<u8>], keystream: &[u8]) -> Vec<Vec<u8>> {
+    ciphertexts
+        .iter()
+        .map(|c| {
+            let len = c.len().min(keystream.len());
+            xor(&c[..len], &keystream[..len])
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes::decrypt_128_ecb;
+    use crate::scorer::EnglishScorer;
+    use crate::utils::{decode_b64, decode_hex, encode_hex, load_file, load_file_per_line};
+
+    #[test]
+    fn test_decrypt_ctr_challenge_18() {
+        let ciphertext =
+            decode_b64("L77na/nrFsKvynd6HzOoG7GHTLXsTVu9qvY/2syLXzhPweyyMTJULu/6/kXX0KSvoOLSFQ==")
+                .unwrap();
+
+        let plaintext = decrypt_128_ctr(b"YELLOW SUBMARINE", 0, &ciphertext).unwrap();
+
+        assert_eq!(
+            b"Yo, VIP Let's kick it Ice, Ice, baby Ice, Ice, baby ".to_vec(),
+            plaintext
+        );
+    }
+
+    #[test]
+    fn test_ctr_sp800_38a() {
+        // NIST SP 800-38A F.5.1, CTR-AES128.Encrypt
+        let key = decode_hex("2b7e151628aed2a6abf7158809cf4f3c").unwrap();
+        let nonce = decode_hex("f0f1f2f3f4f5f6f7f8f9fafb").unwrap();
+        let plaintext =
+            decode_hex("6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51").unwrap();
+
+        let ctr = Ctr::new(
+            Aes128::new(&key).unwrap(),
+            &nonce,
+            CounterLayout::BigEndian96,
+        )
+        .unwrap()
+        .with_initial_counter(0xfcfdfeff);
+
+        assert_eq!(
+            "874d6191b620e3261bef6864990db6ce9806f66b7970fdff8617187bb9fffdff",
+            encode_hex(&ctr.apply_keystream_at(0, &plaintext).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_ctr_seek() {
+        let key = b"YELLOW SUBMARINE";
+        let plaintext: Vec<u8> = (0..100).collect();
+        let ciphertext = encrypt_128_ctr(key, 42, &plaintext).unwrap();
+
+        let mut ctr = Ctr::new(
+            Aes128::new(key).unwrap(),
+            &42u64.to_le_bytes(),
+            CounterLayout::LittleEndian64,
+        )
+        .unwrap();
+
+        assert_eq!(
+            ciphertext[37..61].to_vec(),
+            ctr.apply_keystream_at(37, &plaintext[37..61]).unwrap()
+        );
+
+        ctr.seek(5);
+        let first = ctr.apply_keystream(&plaintext[5..20]).unwrap();
+        let second = ctr.apply_keystream(&plaintext[20..]).unwrap();
+        assert_eq!(100, ctr.position());
+        assert_eq!(ciphertext[5..20].to_vec(), first);
+        assert_eq!(ciphertext[20..].to_vec(), second);
+    }
+
+    #[test]
+    fn test_ctr_counter_overflow() {
+        let ctr = Ctr::new(
+            Aes128::new(b"YELLOW SUBMARINE").unwrap(),
+            &[0; 12],
+            CounterLayout::BigEndian96,
+        )
+        .unwrap()
+        .with_initial_counter(u32::MAX as u64);
+
+        assert!(ctr.counter_block(0).is_ok());
+        assert!(matches!(ctr.counter_block(1), Err(Error::CounterOverflow)));
+        assert!(matches!(
+            Ctr::new(
+                Aes128::new(b"YELLOW SUBMARINE").unwrap(),
+                &[0; 8],
+                CounterLayout::BigEndian96
+            ),
+            Err(Error::InvalidNonceLength(8))
+        ));
+    }
+
+    #[test]
+    fn test_ctr_keystream_near_end_of_stream() {
+        let ctr = Ctr::new(
+            Aes128::new(b"YELLOW SUBMARINE").unwrap(),
+            &[0; 8],
+            CounterLayout::LittleEndian64,
+        )
+        .unwrap();
+
+        assert_eq!(15, ctr.keystream(u64::MAX - 15, 15).unwrap().len());
+        assert!(matches!(
+            ctr.keystream(u64::MAX - 3, 16),
+            Err(Error::CounterOverflow)
+        ));
+        assert!(matches!(
+            ctr.apply_keystream_at(u64::MAX, b"a"),
+            Err(Error::CounterOverflow)
+        ));
+    }
+
+    #[test]
+    fn test_edit() {
+        let key = b"YELLOW SUBMARINE";
+        let ciphertext = encrypt_128_ctr(key, 0, b"Cooking MC's like a pound of bacon").unwrap();
+
+        let edited = edit(&ciphertext, key, 8, b"ROCKERS").unwrap();
+        assert_eq!(ciphertext[..8], edited[..8]);
+        assert_eq!(ciphertext[15..], edited[15..]);
+        assert_eq!(
+            b"Cooking ROCKERSke a pound of bacon".to_vec(),
+            decrypt_128_ctr(key, 0, &edited).unwrap()
+        );
+
+        let extended = edit(&ciphertext, key, 34, b"!!").unwrap();
+        assert_eq!(
+            b"Cooking MC's like a pound of bacon!!".to_vec(),
+            decrypt_128_ctr(key, 0, &extended).unwrap()
+        );
+
+        assert!(matches!(
+            edit(&ciphertext, key, 35, b"!"),
+            Err(Error::InvalidOffset(35))
+        ));
+    }
+
+    #[test]
+    fn test_recover_plaintext_with_edit_challenge_25() {
+        let ciphertext = decode_b64(&load_file("resources/set1/7.txt").unwrap()).unwrap();
+        let plaintext = decrypt_128_ecb(b"YELLOW SUBMARINE", &ciphertext, true).unwrap();
+
+        let oracle = CtrEditOracle::new();
+        let ciphertext = oracle.encrypt(&plaintext).unwrap();
+
+        let recovered = recover_plaintext_with_edit(
+            |c, offset, newtext| oracle.edit(c, offset, newtext),
+            &ciphertext,
+        )
+        .unwrap();
+
+        assert_eq!(plaintext, recovered);
+    }
+
+    fn encrypt_fixed_nonce(plaintexts: &[Vec<u8>]) -> (Vec<Vec<u8>>, Vec<u8>) {
+        let key = random_key(16);
+        let ciphertexts = plaintexts
+            .iter()
+            .map(|p| encrypt_128_ctr(&key, 0, p).unwrap())
+            .collect();
+        let max_len = plaintexts.iter().map(|p| p.len()).max().unwrap();
+        let keystream = encrypt_128_ctr(&key, 0, &vec![0; max_len]).unwrap();
+
+        (ciphertexts, keystream)
+    }
+
+    fn load_plaintexts(path: &str) -> Vec<Vec<u8>> {
+        load_file_per_line(path)
+            .unwrap()
+            .iter()
+            .map(|line| decode_b64(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_break_fixed_nonce_truncated() {
+        let plaintexts = load_plaintexts("resources/set3/19.txt");
+        let (ciphertexts, keystream) = encrypt_fixed_nonce(&plaintexts);
+
+        let recovered = break_fixed_nonce_truncated(&ciphertexts, &EnglishScorer::default());
+        let min_len = plaintexts.iter().map(|p| p.len()).min().unwrap();
+
+        assert_eq!(min_len, recovered.len());
+        // The first column is all capitals and can come out in either case
+        assert_eq!(0, (keystream[0] ^ recovered[0]) & !0x20);
+        assert_eq!(keystream[1..min_len], recovered[1..]);
+    }
+
+    #[test]
+    fn test_break_fixed_nonce_challenge_19() {
+        let plaintexts = load_plaintexts("resources/set3/19.txt");
+        let (ciphertexts, keystream) = encrypt_fixed_nonce(&plaintexts);
+
+        let recovered = break_fixed_nonce(&ciphertexts, &EnglishScorer::default());
+        assert_eq!(keystream.len(), recovered.len());
+
+        // Columns with at least a handful of bytes come out right
+        let deep = (0..keystream.len())
+            .take_while(|&i| ciphertexts.iter().filter(|c| c.len() > i).count() >= 10)
+            .count();
+        assert!(deep > 20);
+        assert_eq!(keystream[1..deep], recovered[1..deep]);
+
+        let decrypted = decrypt_fixed_nonce(&ciphertexts, &recovered);
+        for (plaintext, guess) in plaintexts.iter().zip(decrypted.iter()) {
+            assert_eq!(plaintext.len(), guess.len());
+            let len = plaintext.len().min(deep);
+            assert!(plaintext[..len].eq_ignore_ascii_case(&guess[..len]));
+        }
+    }
+
+    #[test]
+    #[ignore = "needs resources/set3/20.txt from the cryptopals site"]
+    fn test_break_fixed_nonce_challenge_20() {
+        let plaintexts = load_plaintexts("resources/set3/20.txt");
+        let (ciphertexts, keystream) = encrypt_fixed_nonce(&plaintexts);
+
+        let recovered = break_fixed_nonce_truncated(&ciphertexts, &EnglishScorer::default());
+        let min_len = plaintexts.iter().map(|p| p.len()).min().unwrap();
+
+        assert_eq!(min_len, recovered.len());
+        assert_eq!(0, (keystream[0] ^ recovered[0]) & !0x20);
+        assert_eq!(keystream[1..min_len], recovered[1..]);
+    }
+}
@@ -0,0 +1,37 @@
+use std::env;
+use std::process;
+
+use challenge::aes::decrypt_128_ecb;
+use challenge::ctr::{recover_plaintext_with_edit, CtrEditOracle};
+use challenge::utils::{decode_b64, load_file};
+
+fn usage() {
+    println!("Usage: ctr_edit <file base64 encoded aes128 ecb ciphertext> <ecb key>");
+    std::process::exit(-1);
+}
+
+pub fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    if args.len() != 3 {
+        usage();
+        process::exit(1);
+    }
+
+    let ciphertext_b64 = load_file(&args[1]).expect("Unable to read file");
+    let ciphertext_bytes = decode_b64(&ciphertext_b64).expect("Invalid base64");
+    let plaintext =
+        decrypt_128_ecb(args[2].as_bytes(), &ciphertext_bytes, true).expect("Decryption failed");
+
+    // Re-encrypt under CTR with a key only the oracle knows
+    let oracle = CtrEditOracle::new();
+    let ciphertext = oracle.encrypt(&plaintext).expect("Encryption failed");
+
+    let recovered = recover_plaintext_with_edit(
+        |c, offset, newtext| oracle.edit(c, offset, newtext),
+        &ciphertext,
+    )
+    .expect("Attack failed");
+
+    println!("Plaintext:\n{}", String::from_utf8_lossy(&recovered));
+}
@@ -0,0 +1,40 @@
+use std::env;
+use std::process;
+
+use challenge::aes_oracle::{detection_accuracy, minimal_random_method_input};
+
+fn usage() {
+    println!("Usage: detection_stats <trials> [data]");
+    std::process::exit(-1);
+}
+
+pub fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    if args.len() != 2 && args.len() != 3 {
+        usage();
+        process::exit(1);
+    }
+
+    let trials: usize = args[1].parse().expect("Invalid number of trials");
+    let minimal = minimal_random_method_input();
+    let data = match args.get(2) {
+        Some(data) => data.clone().into_bytes(),
+        None => minimal.clone(),
+    };
+
+    let matrix = detection_accuracy(trials, &data).expect("Encryption failed");
+
+    println!("Input length: {}", data.len());
+    println!("                guessed ECB  guessed CBC");
+    println!(
+        "actual ECB  {:>15}  {:>11}",
+        matrix.ecb_as_ecb, matrix.ecb_as_cbc
+    );
+    println!(
+        "actual CBC  {:>15}  {:>11}",
+        matrix.cbc_as_ecb, matrix.cbc_as_cbc
+    );
+    println!("Accuracy: {:.2}%", 100.0 * matrix.accuracy());
+    println!("Minimal input for 100% accuracy: {} bytes", minimal.len());
+}
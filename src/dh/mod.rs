@@ -0,0 +1,297 @@
+// Diffie-Hellman key exchange, and the challenge 34 protocol on top of it:
+// Alice sends p, g and her public key, Bob answers with his, and from then
+// on messages go AES-128-CBC encrypted under a key derived from the shared
+// secret, with a random IV appended to every ciphertext.
+
+use crate::aes;
+use crate::bignum::BigUint;
+use crate::error::{Error, Result};
+use crate::hash::MdHash;
+use crate::random;
+use crate::sha1::Sha1;
+use crate::utils::pkcs_7_padding_validate;
+
+const KEY_SIZE: usize = 16;
+const BLOCK_SIZE: usize = 16;
+const IV_SIZE: usize = BLOCK_SIZE;
+
+const NIST_PRIME: &str = "\
+    ffffffffffffffffc90fdaa22168c234c4c6628b80dc1cd129024e088a67cc\
+    74020bbea63b139b22514a08798e3404ddef9519b3cd3a431b302b0a6df25f\
+    14374fe1356d6d51c245e485b576625e7ec6f44c42e9a637ed6b0bff5cb6f4\
+    06b7edee386bfb5a899fa5ae9f24117c4b1fe649286651ece45b3dc2007cb8\
+    a163bf0598da48361c55d39a69163fa8fd24cf5f83655d23dca3ad961c62f3\
+    56208552bb9ed529077096966d670c354e4abc9804f1746c08ca237327ffff\
+    ffffffffffff";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DhParams {
+    pub p: BigUint,
+    pub g: BigUint,
+}
+
+impl DhParams {
+    pub fn new(p: BigUint, g: BigUint) -> DhParams {
+        DhParams { p, g }
+    }
+
+    // p = 37 and g = 5, small enough to check by hand.
+    pub fn toy() -> DhParams {
+        DhParams::new(BigUint::from(37u32), BigUint::from(5u32))
+    }
+
+    // The 1536-bit MODP group from RFC 3526, with g = 2.
+    pub fn nist() -> DhParams {
+        DhParams::new(
+            BigUint::from_hex(NIST_PRIME).expect("Prime is valid hex"),
+            BigUint::from(2u32),
+        )
+    }
+
+    // A random private key in [1, p - 2] and g^private mod p.
+    pub fn generate_keypair(&self) -> Result<Keypair> {
+        let two = BigUint::from(2u32);
+        let range = self
+            .p
+            .checked_sub(&two)
+            .filter(|range| !range.is_zero())
+            .ok_or(Error::InvalidDhParams)?;
+
+        // Extra random bytes make the bias from the reduction negligible
+        let bytes = random::random_key(self.p.bits() / 8 + 8);
+        let private = &(BigUint::from_bytes_be(&bytes) % &range) + &BigUint::one();
+        let public = self.g.modpow(&private, &self.p)?;
+
+        Ok(Keypair { private, public })
+    }
+}
+
+pub struct Keypair {
+    private: BigUint,
+    pub public: BigUint,
+}
+
+impl Keypair {
+    // other_public^private mod p, which is the same on both sides.
+    pub fn shared_secret(&self, params: &DhParams, other_public: &BigUint) -> Result<BigUint> {
+        other_public.modpow(&self.private, &params.p)
+    }
+}
+
+// The first 16 bytes of SHA-1 of the shared secret as big-endian bytes.
+pub fn derive_key(shared_secret: &BigUint) -> Vec<u8> {
+    Sha1::digest(&shared_secret.to_bytes_be())[..KEY_SIZE].to_vec()
+}
+
+// AES-128-CBC under a random IV, which is appended to the ciphertext.
+pub fn encrypt_message(key: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+    let iv = random::random_key(IV_SIZE);
+    let mut ciphertext = aes::encrypt_128_cbc(key, plaintext, &iv, true)?;
+    ciphertext.extend_from_slice(&iv);
+
+    Ok(ciphertext)
+}
+
+pub fn decrypt_message(key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    if data.len() < IV_SIZE {
+        return Err(Error::InvalidDataLength(data.len()));
+    }
+    let (ciphertext, iv) = data.split_at(data.len() - IV_SIZE);
+
+    let plaintext = aes::decrypt_128_cbc(key, ciphertext, iv)?;
+    pkcs_7_padding_validate(&plaintext, BLOCK_SIZE)
+}
+
+// What goes over the wire between Alice and Bob.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Message {
+    Negotiate { params: DhParams, public: BigUint },
+    PublicKey(BigUint),
+    Encrypted(Vec<u8>),
+}
+
+// One end of the exchange. Alice calls initiate and then complete with
+// Bob's answer, Bob calls accept. After that both can send and receive.
+pub struct Party {
+    params: DhParams,
+    keypair: Keypair,
+    key: Option<Vec<u8>>,
+}
+
+impl Party {
+    pub fn initiate(params: DhParams) -> Result<(Party, Message)> {
+        let keypair = params.generate_keypair()?;
+        let message = Message::Negotiate {
+            params: params.clone(),
+            public: keypair.public.clone(),
+        };
+
+        Ok((
+            Party {
+                params,
+                keypair,
+                key: None,
+            },
+            message,
+        ))
+    }
+
+    pub fn accept(message: &Message) -> Result<(Party, Message)> {
+        let (params, other_public) = match message {
+            Message::Negotiate { params, public } => (params.clone(), public),
+            _ => return Err(Error::UnexpectedMessage),
+        };
+
+        let keypair = params.generate_keypair()?;
+        let key = derive_key(&keypair.shared_secret(&params, other_public)?);
+        let reply = Message::PublicKey(keypair.public.clone());
+
+        Ok((
+            Party {
+                params,
+                keypair,
+                key: Some(key),
+            },
+            reply,
+        ))
+    }
+
+    pub fn complete(&mut self, message: &Message) -> Result<()> {
+        match message {
+            Message::PublicKey(other_public) if self.key.is_none() => {
+                let secret = self.keypair.shared_secret(&self.params, other_public)?;
+                self.key = Some(derive_key(&secret));
+                Ok(())
+            }
+            _ => Err(Error::UnexpectedMessage),
+        }
+    }
+
+    pub fn public_key(&self) -> &BigUint {
+        &self.keypair.public
+    }
+
+    pub fn send(&self, plaintext: &[u8]) -> Result<Message> {
+        let key = self.key.as_ref().ok_or(Error::UnexpectedMessage)?;
+        Ok(Message::Encrypted(encrypt_message(key, plaintext)?))
+    }
+
+    pub fn receive(&self, message: &Message) -> Result<Vec<u8>> {
+        match (message, &self.key) {
+            (Message::Encrypted(data), Some(key)) => decrypt_message(key, data),
+            _ => Err(Error::UnexpectedMessage),
+        }
+    }
+}
+
+// Alice sends a message to Bob, who echoes it back under his own IV.
+// Returns what Alice gets back.
+pub fn echo_exchange(params: DhParams, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let (mut alice, negotiate) = Party::initiate(params)?;
+    let (bob, public_key) = Party::accept(&negotiate)?;
+    alice.complete(&public_key)?;
+
+    let to_bob = alice.send(plaintext)?;
+    let received = bob.receive(&to_bob)?;
+    let to_alice = bob.send(&received)?;
+
+    alice.receive(&to_alice)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toy_shared_secret() {
+        let params = DhParams::toy();
+        let a = params.generate_keypair().unwrap();
+        let b = params.generate_keypair().unwrap();
+
+        let s1 = a.shared_secret(&params, &b.public).unwrap();
+        let s2 = b.shared_secret(&params, &a.public).unwrap();
+        assert_eq!(s1, s2);
+        assert!(s1 < params.p);
+
+        // 5^(3 * 7) mod 37
+        let a = Keypair {
+            private: BigUint::from(3u32),
+            public: BigUint::from(14u32),
+        };
+        assert_eq!(
+            BigUint::from(23u32),
+            a.shared_secret(&params, &BigUint::from(18u32)).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_nist_shared_secret() {
+        let params = DhParams::nist();
+        assert_eq!(1536, params.p.bits());
+
+        let a = params.generate_keypair().unwrap();
+        let b = params.generate_keypair().unwrap();
+        assert_ne!(a.public, b.public);
+
+        let s1 = a.shared_secret(&params, &b.public).unwrap();
+        let s2 = b.shared_secret(&params, &a.public).unwrap();
+        assert_eq!(s1, s2);
+        assert_eq!(derive_key(&s1), derive_key(&s2));
+        assert_eq!(KEY_SIZE, derive_key(&s1).len());
+    }
+
+    #[test]
+    fn test_generate_keypair_invalid_params() {
+        let params = DhParams::new(BigUint::from(2u32), BigUint::from(1u32));
+        assert!(matches!(
+            params.generate_keypair(),
+            Err(Error::InvalidDhParams)
+        ));
+    }
+
+    #[test]
+    fn test_encrypt_message() {
+        let key = random::random_key(KEY_SIZE);
+        let ciphertext = encrypt_message(&key, b"hello bob").unwrap();
+
+        assert_eq!(2 * IV_SIZE, ciphertext.len());
+        assert_eq!(
+            b"hello bob".to_vec(),
+            decrypt_message(&key, &ciphertext).unwrap()
+        );
+        assert!(matches!(
+            decrypt_message(&key, &ciphertext[..8]),
+            Err(Error::InvalidDataLength(8))
+        ));
+    }
+
+    #[test]
+    fn test_echo_exchange() {
+        let message = b"Ice Ice Baby, too cold";
+        assert_eq!(
+            message.to_vec(),
+            echo_exchange(DhParams::nist(), message).unwrap()
+        );
+        assert_eq!(
+            message.to_vec(),
+            echo_exchange(DhParams::toy(), message).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_unexpected_message() {
+        let (mut alice, negotiate) = Party::initiate(DhParams::toy()).unwrap();
+        assert!(matches!(
+            alice.send(b"too early"),
+            Err(Error::UnexpectedMessage)
+        ));
+        assert!(matches!(
+            alice.complete(&negotiate),
+            Err(Error::UnexpectedMessage)
+        ));
+        assert!(matches!(
+            Party::accept(&Message::PublicKey(BigUint::one())),
+            Err(Error::UnexpectedMessage)
+        ));
+    }
+}
@@ -0,0 +1,42 @@
+use std::env;
+use std::process;
+
+use challenge::dh::{derive_key, echo_exchange, DhParams};
+use challenge::utils::encode_hex;
+
+fn usage() {
+    println!("Usage: dh <message> [nist|toy]");
+    std::process::exit(-1);
+}
+
+pub fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    if args.len() != 2 && args.len() != 3 {
+        usage();
+        process::exit(1);
+    }
+
+    let params = match args.get(2).map(|s| s.as_str()) {
+        None | Some("nist") => DhParams::nist(),
+        Some("toy") => DhParams::toy(),
+        Some(_) => {
+            usage();
+            process::exit(1);
+        }
+    };
+
+    let a = params.generate_keypair().expect("Invalid parameters");
+    let b = params.generate_keypair().expect("Invalid parameters");
+    let s_a = a
+        .shared_secret(&params, &b.public)
+        .expect("Invalid parameters");
+    let s_b = b
+        .shared_secret(&params, &a.public)
+        .expect("Invalid parameters");
+    println!("Shared secrets match: {}", s_a == s_b);
+    println!("Key: {}", encode_hex(&derive_key(&s_a)));
+
+    let echo = echo_exchange(params, args[1].as_bytes()).expect("Exchange failed");
+    println!("Echo: {}", String::from_utf8_lossy(&echo));
+}
@@ -1,7 +1,11 @@
 use std::env;
 use std::process;
 
-use challenge::aes_oracle::{detection_oracle_ecb_extra, detection_oracle_ecb_extra_and_random};
+use challenge::aes_oracle::{
+    byte_at_a_time_ecb, byte_at_a_time_ecb_random_prefix, EcbExtraOracle, EcbExtraRandomOracle,
+    ROLLIN_SECRET,
+};
+use challenge::utils::decode_b64;
 
 fn usage() {
     println!("Usage: ecb_byte_at_a_time (simple|harder)");
@@ -16,9 +20,22 @@ pub fn main() {
         process::exit(1);
     }
 
-    if args[1] == "simple" {
-        detection_oracle_ecb_extra();
+    let secret = decode_b64(ROLLIN_SECRET).expect("Invalid base64");
+
+    let result = if args[1] == "simple" {
+        byte_at_a_time_ecb(&EcbExtraOracle::new(&secret))
     } else {
-        detection_oracle_ecb_extra_and_random();
+        byte_at_a_time_ecb_random_prefix(&EcbExtraRandomOracle::new(&secret))
+    };
+
+    match result {
+        Ok((recovered, queries)) => {
+            println!("Queries: {}", queries);
+            println!("Extra text: {:?}", String::from_utf8_lossy(&recovered));
+        }
+        Err(err) => {
+            println!("Attack failed: {}", err);
+            process::exit(1);
+        }
     }
 }
@@ -25,19 +25,22 @@ pub fn main() {
     if args[1] == "encrypt" {
         let key = utils::decode_hex(&args[3]).expect("Invalid hex key");
 
-        let encrypted = cookie::encrypt_cookie(&args[2], &key);
+        let encrypted = cookie::encrypt_cookie(&args[2], &key).expect("Encryption failed");
 
         println!("{:?}", utils::encode_hex(&encrypted));
     } else if args[1] == "decrypt" {
         let cookie = utils::decode_hex(&args[2]).expect("Invalid cookie");
         let key = utils::decode_hex(&args[3]).expect("Invalid hex key");
 
-        println!("{:?}", cookie::decrypt_cookie(cookie, &key));
+        println!(
+            "{:?}",
+            cookie::decrypt_cookie(cookie, &key).expect("Invalid cookie")
+        );
     } else if args[1] == "cut-n-paste" {
         let key = utils::decode_hex(&args[2]).expect("Invalid hex key");
 
         let first = "aaaaaa@aa.com";
-        let encrypted = cookie::encrypt_cookie(first, &key);
+        let encrypted = cookie::encrypt_cookie(first, &key).expect("Encryption failed");
 
         let prefix = "XXXXXXXXXX";
         let admin = String::from("admin").into_bytes();
@@ -46,14 +49,17 @@ pub fn main() {
 
         let cookie_2 = format!("{}{}", prefix, admin_padded_str);
 
-        let encrypted_2 = cookie::encrypt_cookie(&cookie_2, &key);
+        let encrypted_2 = cookie::encrypt_cookie(&cookie_2, &key).expect("Encryption failed");
 
         let mut cutnpaste = encrypted[..32].to_vec();
         let mut cutnpaste_2 = encrypted_2[16..32].to_vec();
 
         cutnpaste.append(&mut cutnpaste_2);
 
-        println!("{:?}", cookie::decrypt_cookie(cutnpaste, &key));
+        println!(
+            "{:?}",
+            cookie::decrypt_cookie(cutnpaste, &key).expect("Invalid cookie")
+        );
     } else {
         println!("Invalid argument");
         usage();
@@ -0,0 +1,81 @@
+use std::fmt;
+use std::io;
+
+#[cfg(feature = "openssl")]
+use openssl::error::ErrorStack;
+
+#[derive(Debug)]
+pub enum Error {
+    InvalidHex,
+    InvalidBase64,
+    InvalidUtf8,
+    InvalidKeyLength(usize),
+    InvalidIvLength(usize),
+    InvalidNonceLength(usize),
+    InvalidDataLength(usize),
+    InvalidOffset(usize),
+    BadPadding,
+    InvalidAscii(Vec<u8>),
+    CounterOverflow,
+    DivisionByZero,
+    InvalidDhParams,
+    UnexpectedMessage,
+    MalformedQueryString(String),
+    AttackFailed(String),
+    Io(io::Error),
+    #[cfg(feature = "openssl")]
+    OpenSsl(ErrorStack),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidHex => write!(f, "invalid hex string"),
+            Error::InvalidBase64 => write!(f, "invalid base64 string"),
+            Error::InvalidUtf8 => write!(f, "invalid utf-8"),
+            Error::InvalidKeyLength(len) => write!(f, "invalid key length {}", len),
+            Error::InvalidIvLength(len) => write!(f, "invalid IV length {}", len),
+            Error::InvalidNonceLength(len) => write!(f, "invalid nonce length {}", len),
+            Error::InvalidDataLength(len) => {
+                write!(f, "data length {} is not a multiple of the block size", len)
+            }
+            Error::InvalidOffset(offset) => {
+                write!(f, "offset {} is past the end of the data", offset)
+            }
+            Error::BadPadding => write!(f, "bad padding"),
+            Error::InvalidAscii(plaintext) => {
+                write!(
+                    f,
+                    "invalid ascii in {:?}",
+                    String::from_utf8_lossy(plaintext)
+                )
+            }
+            Error::CounterOverflow => write!(f, "counter overflow"),
+            Error::DivisionByZero => write!(f, "division by zero"),
+            Error::InvalidDhParams => write!(f, "invalid Diffie-Hellman parameters"),
+            Error::UnexpectedMessage => write!(f, "unexpected message"),
+            Error::MalformedQueryString(part) => write!(f, "malformed query string at {:?}", part),
+            Error::AttackFailed(reason) => write!(f, "attack failed: {}", reason),
+            Error::Io(err) => write!(f, "io error: {}", err),
+            #[cfg(feature = "openssl")]
+            Error::OpenSsl(err) => write!(f, "openssl error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+#[cfg(feature = "openssl")]
+impl From<ErrorStack> for Error {
+    fn from(err: ErrorStack) -> Error {
+        Error::OpenSsl(err)
+    }
+}
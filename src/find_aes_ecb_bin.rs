@@ -35,7 +35,7 @@ pub fn main() {
         process::exit(-1);
     }
 
-    let lines = load_file_per_line(&args[1]);
+    let lines = load_file_per_line(&args[1]).expect("Unable to read file");
 
     let duplicate_counts = lines
         .iter()
@@ -1,12 +1,10 @@
-use std::char;
+use std::cmp::Ordering;
 use std::env;
-use std::fs::File;
-use std::io::BufRead;
-use std::io::BufReader;
 use std::process;
 
-use challenge::utils::decode_hex;
-use challenge::xor::{repeating_key, search_single_char_key, xor};
+use challenge::scorer::EnglishScorer;
+use challenge::utils::{decode_hex, load_file_per_line};
+use challenge::xor::search_single_char_key;
 
 fn usage() {
     println!("Usage: find_single_single_char_xor_string <file>");
@@ -21,28 +19,26 @@ pub fn main() {
         process::exit(1);
     }
 
-    let f = File::open(&args[1]).expect("Unable to open file");
-    let br = BufReader::new(f);
-
-    let mut highest_score = 0;
-    let mut high_score_key = 0;
-    let mut high_score_ciphertext: Vec<u8> = vec![];
-    for line in br.lines() {
-        let l = line.unwrap();
-        let ciphertext = decode_hex(&l).expect("Invalid hex");
-        let (key, score) = search_single_char_key(&ciphertext);
-
-        if score > highest_score {
-            highest_score = score;
-            high_score_key = key;
-            high_score_ciphertext = ciphertext.clone();
-        }
+    let scorer = EnglishScorer::default();
+    let lines = load_file_per_line(&args[1]).expect("Unable to read file");
+
+    // Best candidate for every line, then rank the lines against each other
+    let mut ranked: Vec<(usize, _)> = lines
+        .iter()
+        .map(|line| decode_hex(line).expect("Invalid hex"))
+        .map(|ciphertext| search_single_char_key(&ciphertext, &scorer).remove(0))
+        .enumerate()
+        .collect();
+
+    ranked.sort_by(|(_, a), (_, b)| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+
+    for (line_no, candidate) in ranked.iter().take(3) {
+        println!(
+            "Line: {} Key: {:?} Score: {:.3} Plaintext: {:?}",
+            line_no + 1,
+            candidate.key as char,
+            candidate.score,
+            String::from_utf8_lossy(&candidate.plaintext)
+        );
     }
-
-    println!("Key: {:?}", char::from_u32(high_score_key as u32).unwrap());
-    let key_vec: Vec<u8> = vec![high_score_key];
-    let full_key = repeating_key(&key_vec, high_score_ciphertext.len());
-
-    let plaintext = xor(&high_score_ciphertext, &full_key);
-    println!("Plaintext: {:?}", String::from_utf8(plaintext).unwrap());
 }
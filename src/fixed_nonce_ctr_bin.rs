@@ -0,0 +1,43 @@
+use std::env;
+use std::process;
+
+use challenge::ctr::{break_fixed_nonce, decrypt_fixed_nonce, encrypt_128_ctr};
+use challenge::random::random_key;
+use challenge::scorer::EnglishScorer;
+use challenge::utils::{decode_b64, load_file_per_line};
+
+fn usage() {
+    println!("Usage: fixed_nonce_ctr <file with base64 encoded plaintexts per line>");
+    std::process::exit(-1);
+}
+
+pub fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    if args.len() != 2 {
+        usage();
+        process::exit(1);
+    }
+
+    let lines = load_file_per_line(&args[1]).expect("Unable to read file");
+
+    // Encrypt every line under the same key and nonce
+    let key = random_key(16);
+    let ciphertexts: Vec<Vec<u8>> = lines
+        .iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let plaintext = decode_b64(line).expect("Invalid base64");
+            encrypt_128_ctr(&key, 0, &plaintext).expect("Encryption failed")
+        })
+        .collect();
+    println!("Ciphertexts: {}", ciphertexts.len());
+
+    let keystream = break_fixed_nonce(&ciphertexts, &EnglishScorer::default());
+    println!("Keystream length: {}", keystream.len());
+
+    println!("Plaintexts:");
+    for plaintext in decrypt_fixed_nonce(&ciphertexts, &keystream) {
+        println!("{}", String::from_utf8_lossy(&plaintext));
+    }
+}
@@ -0,0 +1,108 @@
+// Merkle-Damgard hashes whose internal state can be picked up from a digest,
+// which is all that secret-prefix MAC forgery needs to know about them.
+//
+// Implementors only provide the compression function and access to their
+// buffer, length and state words. Buffering, padding and output are shared,
+// as SHA-1 and MD4 only differ in the byte order of words and length there.
+
+use crate::error::{Error, Result};
+
+pub trait MdHash: Sized {
+    const BLOCK_SIZE: usize;
+    const DIGEST_SIZE: usize;
+    // Whether state words and the length are big-endian (SHA-1) or
+    // little-endian (MD4)
+    const BIG_ENDIAN: bool;
+
+    fn new() -> Self;
+
+    // Continues from the state that produced `digest`, as if `length` bytes
+    // (padding included) had already been hashed. Fails unless the digest is
+    // DIGEST_SIZE bytes.
+    fn from_digest(digest: &[u8], length: u64) -> Result<Self>;
+
+    // Processes one BLOCK_SIZE block.
+    fn compress(&mut self, block: &[u8]);
+
+    // Bytes not yet making up a full block.
+    fn buffer_mut(&mut self) -> &mut Vec<u8>;
+
+    // Bytes processed so far, including any counted by from_digest.
+    fn length_mut(&mut self) -> &mut u64;
+
+    fn state(&self) -> &[u32];
+
+    fn update(&mut self, data: &[u8]) {
+        *self.length_mut() += data.len() as u64;
+
+        let mut buffer = std::mem::take(self.buffer_mut());
+        buffer.extend_from_slice(data);
+
+        let full = buffer.len() - buffer.len() % Self::BLOCK_SIZE;
+        for block in buffer[..full].chunks(Self::BLOCK_SIZE) {
+            self.compress(block);
+        }
+        buffer.drain(..full);
+
+        *self.buffer_mut() = buffer;
+    }
+
+    fn finalize(mut self) -> Vec<u8> {
+        let length = *self.length_mut();
+        self.update(&Self::md_padding(length));
+
+        self.state()
+            .iter()
+            .flat_map(|word| {
+                if Self::BIG_ENDIAN {
+                    word.to_be_bytes()
+                } else {
+                    word.to_le_bytes()
+                }
+            })
+            .collect()
+    }
+
+    // The padding appended to a message of `message_len` bytes: 0x80, zeros
+    // up to 8 bytes short of a block and the length in bits as a u64.
+    fn md_padding(message_len: u64) -> Vec<u8> {
+        let block_size = Self::BLOCK_SIZE;
+        let mut padding = vec![0x80];
+        let zeros =
+            (block_size - 1 + block_size - 8 - (message_len as usize % block_size)) % block_size;
+        padding.extend(vec![0; zeros]);
+
+        let bits = message_len.wrapping_mul(8);
+        if Self::BIG_ENDIAN {
+            padding.extend_from_slice(&bits.to_be_bytes());
+        } else {
+            padding.extend_from_slice(&bits.to_le_bytes());
+        }
+
+        padding
+    }
+
+    fn digest(data: &[u8]) -> Vec<u8> {
+        let mut hash = Self::new();
+        hash.update(data);
+        hash.finalize()
+    }
+}
+
+// Reads the state words back out of a digest, for from_digest.
+pub fn state_from_digest<H: MdHash>(digest: &[u8], state: &mut [u32]) -> Result<()> {
+    if digest.len() != H::DIGEST_SIZE || digest.len() != 4 * state.len() {
+        return Err(Error::InvalidDataLength(digest.len()));
+    }
+
+    for (word, chunk) in state.iter_mut().zip(digest.chunks(4)) {
+        let bytes = [chunk[0], chunk[1], chunk[2], chunk[3]];
+        *word = if H::BIG_ENDIAN {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        };
+    }
+
+    Ok(())
+}
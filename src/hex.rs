@@ -16,12 +16,12 @@ pub fn main() {
         let res = decode_hex(&args[1]);
 
         match res {
-            Some(bytes) => {
+            Ok(bytes) => {
                 println!("Decoded: {:?}", &bytes);
                 let encoded = encode_b64(&bytes);
                 println!("Encoded b64: {:?}", encoded);
             }
-            None => {
+            Err(_) => {
                 println!("Invalid hex string");
             }
         }
@@ -1,6 +1,19 @@
 pub mod aes;
 pub mod aes_oracle;
+pub mod bignum;
+pub mod bitflip;
 pub mod cookie;
+pub mod ctr;
+pub mod dh;
+pub mod error;
+pub mod hash;
+pub mod mac;
+pub mod md4;
+pub mod mt_oracle;
+pub mod padding_oracle;
 pub mod random;
+pub mod scorer;
+pub mod sha1;
+pub mod timing_leak;
 pub mod utils;
 pub mod xor;
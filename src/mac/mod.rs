@@ -0,0 +1,213 @@
+use std::marker::PhantomData;
+
+use crate::cookie;
+use crate::error::{Error, Result};
+use crate::hash::MdHash;
+use crate::random;
+
+// H(key || message)
+pub fn secret_prefix_mac<H: MdHash>(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut hash = H::new();
+    hash.update(key);
+    hash.update(message);
+    hash.finalize()
+}
+
+// HMAC as in RFC 2104: H((key ^ opad) || H((key ^ ipad) || message)), the
+// key hashed first if longer than a block.
+pub fn hmac<H: MdHash>(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut block_key = if key.len() > H::BLOCK_SIZE {
+        H::digest(key)
+    } else {
+        key.to_vec()
+    };
+    block_key.resize(H::BLOCK_SIZE, 0);
+
+    let mut inner = H::new();
+    inner.update(&block_key.iter().map(|&b| b ^ 0x36).collect::<Vec<u8>>());
+    inner.update(message);
+
+    let mut outer = H::new();
+    outer.update(&block_key.iter().map(|&b| b ^ 0x5c).collect::<Vec<u8>>());
+    outer.update(&inner.finalize());
+    outer.finalize()
+}
+
+// Signs and verifies messages under a random key of 8 to 32 bytes that the
+// attacker does not know the length of.
+pub struct MacOracle<H: MdHash> {
+    key: Vec<u8>,
+    hash: PhantomData<H>,
+}
+
+impl<H: MdHash> Default for MacOracle<H> {
+    fn default() -> Self {
+        MacOracle::new()
+    }
+}
+
+impl<H: MdHash> MacOracle<H> {
+    pub fn new() -> MacOracle<H> {
+        MacOracle {
+            key: random::random_prefix(8, 33),
+            hash: PhantomData,
+        }
+    }
+
+    pub fn sign(&self, message: &[u8]) -> Vec<u8> {
+        secret_prefix_mac::<H>(&self.key, message)
+    }
+
+    pub fn verify(&self, message: &[u8], mac: &[u8]) -> bool {
+        secret_prefix_mac::<H>(&self.key, message) == mac
+    }
+}
+
+// Whether the message parses as a profile query string ending up with the
+// admin role. The message may contain glue padding, which is not utf-8.
+pub fn is_admin_profile(message: &[u8]) -> Result<bool> {
+    let items = cookie::parse_querystring(&String::from_utf8_lossy(message))?;
+    Ok(cookie::lookup(&items, "role") == Some("admin"))
+}
+
+// Forges a MAC for message || glue padding || extension without the key.
+//
+// The MAC is the hash state after hashing the padded key and message, so
+// hashing can continue from it. The glue padding depends on the key length,
+// which is guessed from 0 up to max_key_len until the oracle accepts.
+// Returns the forged message and its MAC, or an error if the MAC is not a
+// digest of H.
+pub fn forge_length_extension<H, F>(
+    verify: F,
+    message: &[u8],
+    mac: &[u8],
+    extension: &[u8],
+    max_key_len: usize,
+) -> Result<(Vec<u8>, Vec<u8>)>
+where
+    H: MdHash,
+    F: Fn(&[u8], &[u8]) -> bool,
+{
+    for key_len in 0..=max_key_len {
+        let glue = H::md_padding((key_len + message.len()) as u64);
+
+        let mut forged = message.to_vec();
+        forged.extend_from_slice(&glue);
+        forged.extend_from_slice(extension);
+
+        let mut hash = H::from_digest(mac, (key_len + message.len() + glue.len()) as u64)?;
+        hash.update(extension);
+        let forged_mac = hash.finalize();
+
+        if verify(&forged, &forged_mac) {
+            return Ok((forged, forged_mac));
+        }
+    }
+
+    Err(Error::AttackFailed(format!(
+        "no key length up to {} accepted",
+        max_key_len
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::md4::Md4;
+    use crate::sha1::Sha1;
+    use crate::utils::encode_hex;
+
+    #[test]
+    fn test_hmac_sha1_rfc_2202() {
+        assert_eq!(
+            "b617318655057264e28bc0b6fb378c8ef146be00",
+            encode_hex(&hmac::<Sha1>(&[0x0b; 20], b"Hi There"))
+        );
+        assert_eq!(
+            "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79",
+            encode_hex(&hmac::<Sha1>(b"Jefe", b"what do ya want for nothing?"))
+        );
+        assert_eq!(
+            "aa4ae5e15272d00e95705637ce8a3b55ed402112",
+            encode_hex(&hmac::<Sha1>(
+                &[0xaa; 80],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_secret_prefix_mac() {
+        let oracle = MacOracle::<Sha1>::new();
+        let mac = oracle.sign(b"message");
+
+        assert_eq!(Sha1::digest(&[&oracle.key[..], b"message"].concat()), mac);
+        assert!(oracle.verify(b"message", &mac));
+        assert!(!oracle.verify(b"massage", &mac));
+    }
+
+    fn forge_admin_profile<H: MdHash>() {
+        let oracle = MacOracle::<H>::new();
+        let message = cookie::profile_for("foo@bar.com").into_bytes();
+        let mac = oracle.sign(&message);
+        assert!(!is_admin_profile(&message).unwrap());
+
+        let (forged, forged_mac) = forge_length_extension::<H, _>(
+            |m, mac| oracle.verify(m, mac),
+            &message,
+            &mac,
+            b"&role=admin",
+            64,
+        )
+        .unwrap();
+
+        assert!(forged.starts_with(&message));
+        assert!(forged.ends_with(b"&role=admin"));
+        assert!(oracle.verify(&forged, &forged_mac));
+        assert!(is_admin_profile(&forged).unwrap());
+    }
+
+    #[test]
+    fn test_forge_length_extension_sha1() {
+        forge_admin_profile::<Sha1>();
+    }
+
+    #[test]
+    fn test_forge_length_extension_md4() {
+        forge_admin_profile::<Md4>();
+    }
+
+    #[test]
+    fn test_forge_length_extension_key_too_long() {
+        let oracle = MacOracle::<Sha1>::new();
+        let mac = oracle.sign(b"message");
+
+        assert!(matches!(
+            forge_length_extension::<Sha1, _>(
+                |m, mac| oracle.verify(m, mac),
+                b"message",
+                &mac,
+                b"!",
+                4
+            ),
+            Err(Error::AttackFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_forge_length_extension_truncated_mac() {
+        let oracle = MacOracle::<Sha1>::new();
+        let mac = oracle.sign(b"message");
+
+        assert!(matches!(
+            forge_length_extension::<Sha1, _>(
+                |m, mac| oracle.verify(m, mac),
+                b"message",
+                &mac[..10],
+                b"!",
+                64
+            ),
+            Err(Error::InvalidDataLength(10))
+        ));
+    }
+}
@@ -0,0 +1,225 @@
+// MD4 as specified in RFC 1320. Like SHA-1, but with little-endian words
+// and length, and only 48 steps.
+
+use crate::error::Result;
+use crate::hash::{self, MdHash};
+
+pub const BLOCK_SIZE: usize = 64;
+pub const DIGEST_SIZE: usize = 16;
+
+const INITIAL_STATE: [u32; 4] = [0x6745_2301, 0xefcd_ab89, 0x98ba_dcfe, 0x1032_5476];
+
+#[derive(Clone)]
+pub struct Md4 {
+    state: [u32; 4],
+    buffer: Vec<u8>,
+    // Bytes processed so far, including any counted by from_state
+    length: u64,
+}
+
+impl Default for Md4 {
+    fn default() -> Self {
+        Md4::new()
+    }
+}
+
+impl Md4 {
+    // Continues from `state` as if `length` bytes (a multiple of the block
+    // size, padding included) had already been hashed.
+    pub fn from_state(state: [u32; 4], length: u64) -> Md4 {
+        Md4 {
+            state,
+            buffer: Vec::with_capacity(BLOCK_SIZE),
+            length,
+        }
+    }
+
+    // The state a digest was produced from, for use with from_state.
+    pub fn state_from_digest(digest: &[u8]) -> Result<[u32; 4]> {
+        let mut state = [0; 4];
+        hash::state_from_digest::<Md4>(digest, &mut state)?;
+        Ok(state)
+    }
+}
+
+impl MdHash for Md4 {
+    const BLOCK_SIZE: usize = BLOCK_SIZE;
+    const DIGEST_SIZE: usize = DIGEST_SIZE;
+    const BIG_ENDIAN: bool = false;
+
+    fn new() -> Md4 {
+        Md4::from_state(INITIAL_STATE, 0)
+    }
+
+    fn from_digest(digest: &[u8], length: u64) -> Result<Md4> {
+        Ok(Md4::from_state(Md4::state_from_digest(digest)?, length))
+    }
+
+    fn compress(&mut self, block: &[u8]) {
+        let mut x = [0u32; 16];
+        for (i, chunk) in block.chunks(4).enumerate() {
+            x[i] = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+
+        let f = |x: u32, y: u32, z: u32| (x & y) | (!x & z);
+        let g = |x: u32, y: u32, z: u32| (x & y) | (x & z) | (y & z);
+        let h = |x: u32, y: u32, z: u32| x ^ y ^ z;
+
+        let [mut a, mut b, mut c, mut d] = self.state;
+
+        // Round 1
+        for &i in [0, 4, 8, 12].iter() {
+            a = a.wrapping_add(f(b, c, d)).wrapping_add(x[i]).rotate_left(3);
+            d = d
+                .wrapping_add(f(a, b, c))
+                .wrapping_add(x[i + 1])
+                .rotate_left(7);
+            c = c
+                .wrapping_add(f(d, a, b))
+                .wrapping_add(x[i + 2])
+                .rotate_left(11);
+            b = b
+                .wrapping_add(f(c, d, a))
+                .wrapping_add(x[i + 3])
+                .rotate_left(19);
+        }
+
+        // Round 2
+        let k2 = 0x5a82_7999u32;
+        for &i in [0, 1, 2, 3].iter() {
+            a = a
+                .wrapping_add(g(b, c, d))
+                .wrapping_add(x[i])
+                .wrapping_add(k2)
+                .rotate_left(3);
+            d = d
+                .wrapping_add(g(a, b, c))
+                .wrapping_add(x[i + 4])
+                .wrapping_add(k2)
+                .rotate_left(5);
+            c = c
+                .wrapping_add(g(d, a, b))
+                .wrapping_add(x[i + 8])
+                .wrapping_add(k2)
+                .rotate_left(9);
+            b = b
+                .wrapping_add(g(c, d, a))
+                .wrapping_add(x[i + 12])
+                .wrapping_add(k2)
+                .rotate_left(13);
+        }
+
+        // Round 3
+        let k3 = 0x6ed9_eba1u32;
+        for &i in [0, 2, 1, 3].iter() {
+            a = a
+                .wrapping_add(h(b, c, d))
+                .wrapping_add(x[i])
+                .wrapping_add(k3)
+                .rotate_left(3);
+            d = d
+                .wrapping_add(h(a, b, c))
+                .wrapping_add(x[i + 8])
+                .wrapping_add(k3)
+                .rotate_left(9);
+            c = c
+                .wrapping_add(h(d, a, b))
+                .wrapping_add(x[i + 4])
+                .wrapping_add(k3)
+                .rotate_left(11);
+            b = b
+                .wrapping_add(h(c, d, a))
+                .wrapping_add(x[i + 12])
+                .wrapping_add(k3)
+                .rotate_left(15);
+        }
+
+        for (word, value) in self.state.iter_mut().zip([a, b, c, d].iter()) {
+            *word = word.wrapping_add(*value);
+        }
+    }
+
+    fn buffer_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.buffer
+    }
+
+    fn length_mut(&mut self) -> &mut u64 {
+        &mut self.length
+    }
+
+    fn state(&self) -> &[u32] {
+        &self.state
+    }
+}
+
+// The padding MD4 appends to a message of `message_len` bytes.
+pub fn md_padding(message_len: u64) -> Vec<u8> {
+    <Md4 as MdHash>::md_padding(message_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use crate::utils::encode_hex;
+
+    #[test]
+    fn test_md4_rfc_1320() {
+        let vectors: [(&[u8], &str); 7] = [
+            (b"", "31d6cfe0d16ae931b73c59d7e0c089c0"),
+            (b"a", "bde52cb31de33e46245e05fbdbd6fb24"),
+            (b"abc", "a448017aaf21d8525fc10ae87aa6729d"),
+            (b"message digest", "d9130a8164549fe818874806e1c7014b"),
+            (
+                b"abcdefghijklmnopqrstuvwxyz",
+                "d79e1c308aa5bbcdeea8ed63df412da9",
+            ),
+            (
+                b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789",
+                "043f8582f241db351ce627e153e7f0e4",
+            ),
+            (
+                b"12345678901234567890123456789012345678901234567890123456789012345678901234567890",
+                "e33b4ddc9c38f2199c3e7b164fcc0536",
+            ),
+        ];
+
+        for &(message, expected) in vectors.iter() {
+            assert_eq!(expected, encode_hex(&Md4::digest(message)));
+        }
+    }
+
+    #[test]
+    fn test_md4_from_state() {
+        let message = b"abc";
+        let mut glued = message.to_vec();
+        glued.extend(md_padding(message.len() as u64));
+        glued.extend_from_slice(b"def");
+
+        let mut md4 = Md4::from_digest(&Md4::digest(message), BLOCK_SIZE as u64).unwrap();
+        md4.update(b"def");
+
+        assert_eq!(Md4::digest(&glued), md4.finalize());
+    }
+
+    #[test]
+    fn test_md_padding_little_endian() {
+        let padding = md_padding(3);
+        assert_eq!(61, padding.len());
+        assert_eq!([24, 0, 0, 0, 0, 0, 0, 0], padding[53..]);
+    }
+
+    #[test]
+    fn test_state_from_digest_wrong_length() {
+        let digest = Md4::digest(b"abc");
+
+        assert!(matches!(
+            Md4::state_from_digest(&digest[..15]),
+            Err(Error::InvalidDataLength(15))
+        ));
+        assert!(matches!(
+            Md4::from_digest(&[0; 20], 64),
+            Err(Error::InvalidDataLength(20))
+        ));
+    }
+}
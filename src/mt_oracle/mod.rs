@@ -0,0 +1,191 @@
+use std::cell::Cell;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::random::{random_prefix, random_usize, Mt19937};
+use crate::utils::encode_hex;
+use crate::xor::xor;
+
+// Source of Unix time in seconds. Targets that wait and then read the time
+// take a clock, so tests can pass a MockClock instead of sleeping.
+pub trait Clock {
+    fn now(&self) -> u64;
+
+    fn sleep(&self, seconds: u64);
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Clock is before 1970")
+            .as_secs()
+    }
+
+    fn sleep(&self, seconds: u64) {
+        thread::sleep(Duration::from_secs(seconds));
+    }
+}
+
+// A clock that only moves when slept on.
+pub struct MockClock {
+    now: Cell<u64>,
+}
+
+impl MockClock {
+    pub fn new(now: u64) -> MockClock {
+        MockClock {
+            now: Cell::new(now),
+        }
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> u64 {
+        self.now.get()
+    }
+
+    fn sleep(&self, seconds: u64) {
+        self.now.set(self.now.get() + seconds);
+    }
+}
+
+// The challenge 22 target: waits 40 to 1000 seconds, seeds MT19937 with the
+// current time, waits again and returns the first output.
+pub fn timestamp_seeded_output<C: Clock>(clock: &C) -> u32 {
+    clock.sleep(random_usize(40, 1001) as u64);
+    let mut mt = Mt19937::new(clock.now() as u32);
+    clock.sleep(random_usize(40, 1001) as u64);
+
+    mt.next_u32()
+}
+
+// Finds the seed among the timestamps from `now - window` up to `now`, most
+// recent first.
+pub fn recover_timestamp_seed(output: u32, now: u64, window: u64) -> Option<u32> {
+    (now.saturating_sub(window)..=now)
+        .rev()
+        .map(|timestamp| timestamp as u32)
+        .find(|&seed| Mt19937::new(seed).next_u32() == output)
+}
+
+// Keystream of the challenge 24 stream cipher, every output of MT19937
+// giving four bytes, little-endian.
+pub fn mt_keystream(seed: u32, len: usize) -> Vec<u8> {
+    let mut mt = Mt19937::new(seed);
+    let mut keystream = Vec::with_capacity(len + 4);
+    while keystream.len() < len {
+        keystream.extend_from_slice(&mt.next_u32().to_le_bytes());
+    }
+    keystream.truncate(len);
+
+    keystream
+}
+
+pub fn mt_encrypt(seed: u16, data: &[u8]) -> Vec<u8> {
+    xor(data, &mt_keystream(seed as u32, data.len()))
+}
+
+pub fn mt_decrypt(seed: u16, data: &[u8]) -> Vec<u8> {
+    mt_encrypt(seed, data)
+}
+
+// Encrypts the known plaintext after 5 to 40 random bytes.
+pub fn mt_encrypt_with_random_prefix(seed: u16, known: &[u8]) -> Vec<u8> {
+    let mut plaintext = random_prefix(5, 41);
+    plaintext.extend_from_slice(known);
+
+    mt_encrypt(seed, &plaintext)
+}
+
+// Tries all 2^16 seeds, looking for one that decrypts the end of the
+// ciphertext to the known suffix.
+pub fn recover_mt_seed(ciphertext: &[u8], known_suffix: &[u8]) -> Option<u16> {
+    if known_suffix.len() > ciphertext.len() {
+        return None;
+    }
+    let offset = ciphertext.len() - known_suffix.len();
+    let expected = xor(&ciphertext[offset..], known_suffix);
+
+    (0..=u16::MAX)
+        .find(|&seed| mt_keystream(seed as u32, ciphertext.len())[offset..] == expected[..])
+}
+
+const TOKEN_SIZE: usize = 16;
+
+// A password reset token, 16 bytes of MT19937 output seeded with the current
+// time, hex encoded.
+pub fn password_reset_token<C: Clock>(clock: &C) -> String {
+    encode_hex(&mt_keystream(clock.now() as u32, TOKEN_SIZE))
+}
+
+// Whether the token came from password_reset_token in the last `window`
+// seconds.
+pub fn is_timestamp_token<C: Clock>(token: &str, clock: &C, window: u64) -> bool {
+    let now = clock.now();
+    (now.saturating_sub(window)..=now)
+        .any(|timestamp| encode_hex(&mt_keystream(timestamp as u32, TOKEN_SIZE)) == token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::random_key;
+
+    #[test]
+    fn test_recover_timestamp_seed() {
+        let start = 1_600_000_000;
+        let clock = MockClock::new(start);
+
+        let output = timestamp_seeded_output(&clock);
+        assert!(clock.now() >= start + 80);
+
+        let seed = recover_timestamp_seed(output, clock.now(), 2000).unwrap();
+        assert!(seed as u64 > start && (seed as u64) < clock.now());
+        assert_eq!(output, Mt19937::new(seed).next_u32());
+    }
+
+    #[test]
+    fn test_recover_timestamp_seed_outside_window() {
+        let output = Mt19937::new(1_600_000_000).next_u32();
+
+        assert_eq!(None, recover_timestamp_seed(output, 1_600_001_000, 100));
+        assert_eq!(
+            Some(1_600_000_000),
+            recover_timestamp_seed(output, 1_600_000_100, 100)
+        );
+    }
+
+    #[test]
+    fn test_mt_encrypt_decrypt() {
+        let plaintext = b"Cooking MC's like a pound of bacon".to_vec();
+        let ciphertext = mt_encrypt(1234, &plaintext);
+
+        assert_ne!(plaintext, ciphertext);
+        assert_eq!(plaintext, mt_decrypt(1234, &ciphertext));
+    }
+
+    #[test]
+    fn test_recover_mt_seed() {
+        let seed = random_usize(0, 1 << 16) as u16;
+        let ciphertext = mt_encrypt_with_random_prefix(seed, b"AAAAAAAAAAAAAA");
+
+        assert_eq!(Some(seed), recover_mt_seed(&ciphertext, b"AAAAAAAAAAAAAA"));
+    }
+
+    #[test]
+    fn test_password_reset_token() {
+        let clock = MockClock::new(1_600_000_000);
+        let token = password_reset_token(&clock);
+        assert_eq!(32, token.len());
+
+        clock.sleep(300);
+        assert!(is_timestamp_token(&token, &clock, 600));
+        assert!(!is_timestamp_token(&token, &clock, 100));
+
+        let random_token = encode_hex(&random_key(16));
+        assert!(!is_timestamp_token(&random_token, &clock, 600));
+    }
+}
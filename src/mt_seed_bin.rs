@@ -0,0 +1,30 @@
+use std::env;
+use std::process;
+
+use challenge::mt_oracle::{recover_timestamp_seed, Clock, SystemClock};
+
+fn usage() {
+    println!("Usage: mt_seed <first MT19937 output> <window in seconds>");
+    std::process::exit(-1);
+}
+
+pub fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    if args.len() != 3 {
+        usage();
+        process::exit(1);
+    }
+
+    let output: u32 = args[1].parse().expect("Invalid output");
+    let window: u64 = args[2].parse().expect("Invalid window");
+
+    let now = SystemClock.now();
+    match recover_timestamp_seed(output, now, window) {
+        Some(seed) => println!("Seed: {}", seed),
+        None => {
+            println!("No seed found in the last {} seconds", window);
+            process::exit(1);
+        }
+    }
+}
@@ -18,9 +18,10 @@ pub fn main() {
     }
     let data = args[1].clone().into_bytes();
 
-    let encrypted = encrypt_random_method(&data);
+    let (encrypted, actual_method) = encrypt_random_method(&data).expect("Encryption failed");
 
     println!("Encrypted: {}", encode_hex(&encrypted));
     let guessed_method = detection_oracle_random_method(&encrypted);
     println!("Guessed method: {:?}", guessed_method);
+    println!("Actual method: {:?}", actual_method);
 }
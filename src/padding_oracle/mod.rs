@@ -0,0 +1,175 @@
+use crate::aes;
+use crate::error::{Error, Result};
+use crate::random;
+use crate::utils;
+use crate::xor::xor;
+
+pub const CHALLENGE_17_STRINGS: [&str; 10] = [
+    "MDAwMDAwTm93IHRoYXQgdGhlIHBhcnR5IGlzIGp1bXBpbmc=",
+    "MDAwMDAxV2l0aCB0aGUgYmFzcyBraWNrZWQgaW4gYW5kIHRoZSBWZWdhJ3MgYXJlIHB1bXBpbic=",
+    "MDAwMDAyUXVpY2sgdG8gdGhlIHBvaW50LCB0byB0aGUgcG9pbnQsIG5vIGZha2luZw==",
+    "MDAwMDAzQ29va2luZyBNQ3MgbGlrZSBhIHBvdW5kIG9mIGJhY29u",
+    "MDAwMDA0QnVybmluZyAnZW0sIGlmIHlvdSBhaW4ndCBxdWljayBhbmQgbmltYmxl",
+    "MDAwMDA1SSBnbyBjcmF6eSB3aGVuIEkgaGVhciBhIGN5bWJhbA==",
+    "MDAwMDA2QW5kIGEgaGlnaCBoYXQgd2l0aCBhIHNvdXBlZCB1cCB0ZW1wbw==",
+    "MDAwMDA3SSdtIG9uIGEgcm9sbCwgaXQncyB0aW1lIHRvIGdvIHNvbG8=",
+    "MDAwMDA4b2xsaW4nIGluIG15IGZpdmUgcG9pbnQgb2g=",
+    "MDAwMDA5aXRoIG15IHJhZy10b3AgZG93biBzbyBteSBoYWlyIGNhbiBibG93",
+];
+
+// The challenge 17 server: encrypts under a fixed random key and answers
+// whether a ciphertext decrypts to valid padding.
+pub struct PaddingOracleTarget {
+    key: Vec<u8>,
+}
+
+impl Default for PaddingOracleTarget {
+    fn default() -> Self {
+        PaddingOracleTarget::new()
+    }
+}
+
+impl PaddingOracleTarget {
+    pub fn new() -> PaddingOracleTarget {
+        PaddingOracleTarget {
+            key: random::random_key(16),
+        }
+    }
+
+    // Returns (iv, ciphertext)
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+        let iv = random::random_key(16);
+        let ciphertext = aes::encrypt_128_cbc(&self.key, plaintext, &iv, true)?;
+        Ok((iv, ciphertext))
+    }
+
+    pub fn encrypt_random_string(&self) -> Result<(Vec<u8>, Vec<u8>)> {
+        let index = random::random_usize(0, CHALLENGE_17_STRINGS.len());
+        let plaintext = utils::decode_b64(CHALLENGE_17_STRINGS[index])?;
+        self.encrypt(&plaintext)
+    }
+
+    pub fn padding_valid(&self, iv: &[u8], ciphertext: &[u8]) -> bool {
+        match aes::decrypt_128_cbc(&self.key, ciphertext, iv) {
+            Ok(plaintext) => utils::pkcs_7_padding_validate(&plaintext, 16).is_ok(),
+            Err(_) => false,
+        }
+    }
+}
+
+// Recovers the plaintext of a single ciphertext block, given the block
+// before it (or the IV).
+//
+// For each byte, from the last one, a forged IV is searched for that makes
+// the block decrypt with valid padding. Knowing the forged byte and the
+// padding value gives the intermediate (pre-xor) byte.
+pub fn attack_block<F>(oracle: &F, prev: &[u8], block: &[u8]) -> Result<Vec<u8>>
+where
+    F: Fn(&[u8], &[u8]) -> bool,
+{
+    let block_size = block.len();
+    let mut intermediate = vec![0; block_size];
+
+    for pad in 1..=block_size {
+        let pos = block_size - pad;
+
+        let mut forged = vec![0; block_size];
+        for i in pos + 1..block_size {
+            forged[i] = intermediate[i] ^ pad as u8;
+        }
+
+        let mut found = None;
+        for guess in 0..=255 {
+            forged[pos] = guess;
+            if !oracle(&forged, block) {
+                continue;
+            }
+
+            // On the last byte the padding could also have come out as
+            // \x02\x02 (or longer) by chance. Changing the byte before it
+            // only keeps the padding valid if it really was \x01.
+            if pad == 1 && pos > 0 {
+                let mut check = forged.clone();
+                check[pos - 1] ^= 0xFF;
+                if !oracle(&check, block) {
+                    continue;
+                }
+            }
+
+            found = Some(guess);
+            break;
+        }
+
+        let guess = found.ok_or_else(|| {
+            Error::AttackFailed(format!("no valid padding found for byte {}", pos))
+        })?;
+        intermediate[pos] = guess ^ pad as u8;
+    }
+
+    Ok(xor(&intermediate, prev))
+}
+
+// Recovers the full plaintext, with the padding removed, using only an
+// oracle telling whether (iv, ciphertext) decrypts to valid padding.
+pub fn attack<F>(oracle: F, iv: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>>
+where
+    F: Fn(&[u8], &[u8]) -> bool,
+{
+    let block_size = iv.len();
+    if block_size == 0 {
+        return Err(Error::InvalidIvLength(0));
+    }
+    if ciphertext.is_empty() || !ciphertext.len().is_multiple_of(block_size) {
+        return Err(Error::InvalidDataLength(ciphertext.len()));
+    }
+
+    let mut plaintext = Vec::with_capacity(ciphertext.len());
+    let mut prev = iv;
+
+    for block in ciphertext.chunks(block_size) {
+        plaintext.extend(attack_block(&oracle, prev, block)?);
+        prev = block;
+    }
+
+    utils::pkcs_7_padding_validate(&plaintext, block_size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attack_challenge_17_strings() {
+        let target = PaddingOracleTarget::new();
+
+        for string in CHALLENGE_17_STRINGS.iter() {
+            let expected = utils::decode_b64(string).unwrap();
+            let (iv, ciphertext) = target.encrypt(&expected).unwrap();
+
+            let recovered = attack(|iv, c| target.padding_valid(iv, c), &iv, &ciphertext);
+
+            assert_eq!(expected, recovered.unwrap());
+        }
+    }
+
+    #[test]
+    fn test_attack_block_false_02_02_padding() {
+        // Pick the plaintext so the intermediate state ends in \x02\x02. With
+        // a zeroed forged IV the guess giving \x02\x02 is then found before
+        // the one giving \x01.
+        let key = random::random_key(16);
+        let iv = random::random_key(16);
+        let mut plaintext = random::random_key(16);
+        plaintext[14] = iv[14] ^ 0x02;
+        plaintext[15] = iv[15] ^ 0x02;
+
+        let ciphertext = aes::encrypt_128_cbc(&key, &plaintext, &iv, false).unwrap();
+
+        let oracle = |iv: &[u8], c: &[u8]| {
+            let decrypted = aes::decrypt_128_cbc(&key, c, iv).unwrap();
+            utils::pkcs_7_padding_validate(&decrypted, 16).is_ok()
+        };
+
+        assert_eq!(plaintext, attack_block(&oracle, &iv, &ciphertext).unwrap());
+    }
+}
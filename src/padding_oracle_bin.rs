@@ -0,0 +1,16 @@
+use challenge::padding_oracle::{attack, PaddingOracleTarget, CHALLENGE_17_STRINGS};
+use challenge::utils::decode_b64;
+
+pub fn main() {
+    let target = PaddingOracleTarget::new();
+
+    for string in CHALLENGE_17_STRINGS.iter() {
+        let plaintext = decode_b64(string).expect("Invalid base64");
+        let (iv, ciphertext) = target.encrypt(&plaintext).expect("Encryption failed");
+
+        let recovered =
+            attack(|iv, c| target.padding_valid(iv, c), &iv, &ciphertext).expect("Attack failed");
+
+        println!("{}", String::from_utf8_lossy(&recovered));
+    }
+}
@@ -1,8 +1,18 @@
+pub mod mt19937;
+
 use rand::{OsRng, Rng};
 
+pub use self::mt19937::{clone_from_outputs, untemper, Mt19937, Mt19937_64};
+
 pub fn random_key(size: usize) -> Vec<u8> {
-    let mut key = vec![0; size];
     let mut rng = OsRng::new().expect("Cant load random");
+    random_key_from(&mut rng, size)
+}
+
+// Same as random_key, but from the given generator. With a seeded Mt19937
+// this gives the same key every time.
+pub fn random_key_from<R: Rng>(rng: &mut R, size: usize) -> Vec<u8> {
+    let mut key = vec![0; size];
     rng.fill_bytes(&mut key);
 
     key
@@ -13,9 +23,17 @@ pub fn random_bool() -> bool {
     rng.gen_weighted_bool(2)
 }
 
+pub fn random_usize(min: usize, max: usize) -> usize {
+    let mut rng = OsRng::new().expect("Cant load random");
+    rng.gen_range(min, max)
+}
+
 pub fn random_prefix(min: usize, max: usize) -> Vec<u8> {
     let mut rng = OsRng::new().expect("Cant load random");
+    random_prefix_from(&mut rng, min, max)
+}
 
+pub fn random_prefix_from<R: Rng>(rng: &mut R, min: usize, max: usize) -> Vec<u8> {
     let len = rng.gen_range(min, max);
 
     let mut prefix = vec![0; len];
@@ -24,3 +42,23 @@ pub fn random_prefix(min: usize, max: usize) -> Vec<u8> {
 
     prefix
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_from_seeded_mt19937() {
+        let mut a = Mt19937::new(1234);
+        let mut b = Mt19937::new(1234);
+
+        assert_eq!(random_key_from(&mut a, 16), random_key_from(&mut b, 16));
+
+        let prefix = random_prefix_from(&mut a, 5, 11);
+        assert!(prefix.len() >= 5 && prefix.len() < 11);
+        assert_eq!(prefix, random_prefix_from(&mut b, 5, 11));
+
+        let mut c = Mt19937_64::new(1234);
+        assert_ne!(random_key_from(&mut a, 16), random_key_from(&mut c, 16));
+    }
+}
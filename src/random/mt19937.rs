@@ -0,0 +1,336 @@
+use rand::Rng;
+
+// MT19937 as described in Matsumoto and Nishimura's reference code
+// (mt19937ar.c and mt19937-64.c), including the init_by_array seeding.
+
+// Number of 32 bit words of state, and so also the number of consecutive
+// outputs needed to clone a generator.
+pub const STATE_SIZE: usize = 624;
+
+const N: usize = STATE_SIZE;
+const M: usize = 397;
+const MATRIX_A: u32 = 0x9908_b0df;
+const UPPER_MASK: u32 = 0x8000_0000;
+const LOWER_MASK: u32 = 0x7fff_ffff;
+
+pub struct Mt19937 {
+    state: [u32; N],
+    index: usize,
+}
+
+impl Mt19937 {
+    pub fn new(seed: u32) -> Mt19937 {
+        let mut state = [0; N];
+        state[0] = seed;
+        for i in 1..N {
+            let prev = state[i - 1];
+            state[i] = 1_812_433_253u32
+                .wrapping_mul(prev ^ (prev >> 30))
+                .wrapping_add(i as u32);
+        }
+
+        Mt19937 { state, index: N }
+    }
+
+    // A generator that twists its state before the next output, i.e. one
+    // that has just used up `state`.
+    pub fn from_state(state: [u32; N]) -> Mt19937 {
+        Mt19937 { state, index: N }
+    }
+
+    pub fn from_array(key: &[u32]) -> Mt19937 {
+        let mut mt = Mt19937::new(19_650_218);
+        let state = &mut mt.state;
+
+        let mut i = 1;
+        let mut j = 0;
+        for _ in 0..N.max(key.len()) {
+            let prev = state[i - 1];
+            state[i] = (state[i] ^ (prev ^ (prev >> 30)).wrapping_mul(1_664_525))
+                .wrapping_add(key.get(j).copied().unwrap_or(0))
+                .wrapping_add(j as u32);
+            i += 1;
+            j += 1;
+            if i >= N {
+                state[0] = state[N - 1];
+                i = 1;
+            }
+            if j >= key.len() {
+                j = 0;
+            }
+        }
+        for _ in 0..N - 1 {
+            let prev = state[i - 1];
+            state[i] = (state[i] ^ (prev ^ (prev >> 30)).wrapping_mul(1_566_083_941))
+                .wrapping_sub(i as u32);
+            i += 1;
+            if i >= N {
+                state[0] = state[N - 1];
+                i = 1;
+            }
+        }
+        state[0] = 0x8000_0000;
+
+        mt
+    }
+
+    fn twist(&mut self) {
+        for i in 0..N {
+            let y = (self.state[i] & UPPER_MASK) | (self.state[(i + 1) % N] & LOWER_MASK);
+            let mut next = self.state[(i + M) % N] ^ (y >> 1);
+            if y & 1 != 0 {
+                next ^= MATRIX_A;
+            }
+            self.state[i] = next;
+        }
+        self.index = 0;
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        if self.index >= N {
+            self.twist();
+        }
+
+        let y = self.state[self.index];
+        self.index += 1;
+
+        temper(y)
+    }
+}
+
+fn temper(mut y: u32) -> u32 {
+    y ^= y >> 11;
+    y ^= (y << 7) & 0x9d2c_5680;
+    y ^= (y << 15) & 0xefc6_0000;
+    y ^ (y >> 18)
+}
+
+// Inverts y ^= y >> shift. Every pass gets another `shift` bits right, from
+// the top.
+fn undo_right_shift(y: u32, shift: u32) -> u32 {
+    let mut x = y;
+    for _ in 0..32 / shift {
+        x = y ^ (x >> shift);
+    }
+    x
+}
+
+// Inverts y ^= (y << shift) & mask, getting `shift` bits right from the
+// bottom every pass.
+fn undo_left_shift(y: u32, shift: u32, mask: u32) -> u32 {
+    let mut x = y;
+    for _ in 0..32 / shift {
+        x = y ^ ((x << shift) & mask);
+    }
+    x
+}
+
+// Recovers the state word that next_u32 tempered into `y`.
+pub fn untemper(y: u32) -> u32 {
+    let y = undo_right_shift(y, 18);
+    let y = undo_left_shift(y, 15, 0xefc6_0000);
+    let y = undo_left_shift(y, 7, 0x9d2c_5680);
+    undo_right_shift(y, 11)
+}
+
+// Clones a generator from STATE_SIZE consecutive outputs, starting right
+// after a twist (which is where a freshly seeded generator starts). The
+// clone then produces the same outputs as the original from that point on.
+pub fn clone_from_outputs(outputs: &[u32; N]) -> Mt19937 {
+    let mut state = [0; N];
+    for (word, &output) in state.iter_mut().zip(outputs.iter()) {
+        *word = untemper(output);
+    }
+
+    Mt19937::from_state(state)
+}
+
+impl Rng for Mt19937 {
+    fn next_u32(&mut self) -> u32 {
+        Mt19937::next_u32(self)
+    }
+}
+
+const N_64: usize = 312;
+const M_64: usize = 156;
+const MATRIX_A_64: u64 = 0xb502_6f5a_a966_19e9;
+const UPPER_MASK_64: u64 = 0xffff_ffff_8000_0000;
+const LOWER_MASK_64: u64 = 0x7fff_ffff;
+
+#[allow(non_camel_case_types)]
+pub struct Mt19937_64 {
+    state: [u64; N_64],
+    index: usize,
+}
+
+impl Mt19937_64 {
+    pub fn new(seed: u64) -> Mt19937_64 {
+        let mut state = [0; N_64];
+        state[0] = seed;
+        for i in 1..N_64 {
+            let prev = state[i - 1];
+            state[i] = 6_364_136_223_846_793_005u64
+                .wrapping_mul(prev ^ (prev >> 62))
+                .wrapping_add(i as u64);
+        }
+
+        Mt19937_64 { state, index: N_64 }
+    }
+
+    pub fn from_array(key: &[u64]) -> Mt19937_64 {
+        let mut mt = Mt19937_64::new(19_650_218);
+        let state = &mut mt.state;
+
+        let mut i = 1;
+        let mut j = 0;
+        for _ in 0..N_64.max(key.len()) {
+            let prev = state[i - 1];
+            state[i] = (state[i] ^ (prev ^ (prev >> 62)).wrapping_mul(3_935_559_000_370_003_845))
+                .wrapping_add(key.get(j).copied().unwrap_or(0))
+                .wrapping_add(j as u64);
+            i += 1;
+            j += 1;
+            if i >= N_64 {
+                state[0] = state[N_64 - 1];
+                i = 1;
+            }
+            if j >= key.len() {
+                j = 0;
+            }
+        }
+        for _ in 0..N_64 - 1 {
+            let prev = state[i - 1];
+            state[i] = (state[i] ^ (prev ^ (prev >> 62)).wrapping_mul(2_862_933_555_777_941_757))
+                .wrapping_sub(i as u64);
+            i += 1;
+            if i >= N_64 {
+                state[0] = state[N_64 - 1];
+                i = 1;
+            }
+        }
+        state[0] = 1 << 63;
+
+        mt
+    }
+
+    fn twist(&mut self) {
+        for i in 0..N_64 {
+            let x = (self.state[i] & UPPER_MASK_64) | (self.state[(i + 1) % N_64] & LOWER_MASK_64);
+            let mut next = self.state[(i + M_64) % N_64] ^ (x >> 1);
+            if x & 1 != 0 {
+                next ^= MATRIX_A_64;
+            }
+            self.state[i] = next;
+        }
+        self.index = 0;
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        if self.index >= N_64 {
+            self.twist();
+        }
+
+        let mut x = self.state[self.index];
+        self.index += 1;
+
+        x ^= (x >> 29) & 0x5555_5555_5555_5555;
+        x ^= (x << 17) & 0x71d6_7fff_eda6_0000;
+        x ^= (x << 37) & 0xfff7_eee0_0000_0000;
+        x ^ (x >> 43)
+    }
+}
+
+impl Rng for Mt19937_64 {
+    fn next_u32(&mut self) -> u32 {
+        (Mt19937_64::next_u64(self) >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        Mt19937_64::next_u64(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mt19937_seed() {
+        let mut mt = Mt19937::new(5489);
+        assert_eq!(3_499_211_612, mt.next_u32());
+        for _ in 0..9998 {
+            mt.next_u32();
+        }
+        // The 10000th output, as required of std::mt19937 by C++11
+        assert_eq!(4_123_659_995, mt.next_u32());
+
+        assert_eq!(1_608_637_542, Mt19937::new(42).next_u32());
+    }
+
+    #[test]
+    fn test_mt19937_from_array() {
+        // First outputs listed in mt19937ar.out
+        let mut mt = Mt19937::from_array(&[0x123, 0x234, 0x345, 0x456]);
+        let outputs: Vec<u32> = (0..5).map(|_| mt.next_u32()).collect();
+
+        assert_eq!(
+            vec![
+                1_067_595_299,
+                955_945_823,
+                477_289_528,
+                4_107_218_783,
+                4_228_976_476
+            ],
+            outputs
+        );
+    }
+
+    #[test]
+    fn test_untemper() {
+        for &y in [0, 1, 0xffff_ffff, 0x8000_0000, 0xdead_beef, 3_499_211_612].iter() {
+            assert_eq!(y, untemper(temper(y)));
+            assert_eq!(y, temper(untemper(y)));
+        }
+    }
+
+    #[test]
+    fn test_clone_from_outputs() {
+        let mut mt = Mt19937::new(0x1234_5678);
+
+        let mut outputs = [0; STATE_SIZE];
+        for output in outputs.iter_mut() {
+            *output = mt.next_u32();
+        }
+
+        let mut clone = clone_from_outputs(&outputs);
+        for _ in 0..10_000 {
+            assert_eq!(mt.next_u32(), clone.next_u32());
+        }
+    }
+
+    #[test]
+    fn test_mt19937_64_seed() {
+        let mut mt = Mt19937_64::new(5489);
+        assert_eq!(14_514_284_786_278_117_030, mt.next_u64());
+        for _ in 0..9998 {
+            mt.next_u64();
+        }
+        assert_eq!(9_981_545_732_273_789_042, mt.next_u64());
+    }
+
+    #[test]
+    fn test_mt19937_64_from_array() {
+        // First outputs listed in mt19937-64.out
+        let mut mt = Mt19937_64::from_array(&[0x12345, 0x23456, 0x34567, 0x45678]);
+        let outputs: Vec<u64> = (0..3).map(|_| mt.next_u64()).collect();
+
+        assert_eq!(
+            vec![
+                7_266_447_313_870_364_031,
+                4_946_485_549_665_804_864,
+                16_945_909_448_695_747_420
+            ],
+            outputs
+        );
+    }
+}
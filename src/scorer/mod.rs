@@ -0,0 +1,259 @@
+// Scoring of candidate plaintexts by how much they look like English.
+//
+// All scorers return higher scores for more English-like text, so candidates
+// from different keys can be ranked by sorting on the score.
+
+pub trait Scorer {
+    fn score(&self, plaintext: &[u8]) -> f64;
+}
+
+// Relative frequency of a-z in English text, in percent.
+const LETTER_FREQUENCIES: [f64; 26] = [
+    8.167, 1.492, 2.782, 4.253, 12.702, 2.228, 2.015, 6.094, 6.966, 0.153, 0.772, 4.025, 2.406,
+    6.749, 7.507, 1.929, 0.095, 5.987, 6.327, 9.056, 2.758, 0.978, 2.360, 0.150, 1.974, 0.074,
+];
+
+// Share of all characters that are letters, spaces and anything else
+// printable (digits, punctuation, newlines).
+const LETTER_SHARE: f64 = 0.80;
+const SPACE_SHARE: f64 = 0.17;
+const OTHER_SHARE: f64 = 0.03;
+
+// The 50 most common letter bigrams in English, in percent.
+const BIGRAM_FREQUENCIES: [(&[u8; 2], f64); 50] = [
+    (b"th", 3.56),
+    (b"he", 3.07),
+    (b"in", 2.43),
+    (b"er", 2.05),
+    (b"an", 1.99),
+    (b"re", 1.85),
+    (b"on", 1.76),
+    (b"at", 1.49),
+    (b"en", 1.45),
+    (b"nd", 1.35),
+    (b"ti", 1.34),
+    (b"es", 1.34),
+    (b"or", 1.28),
+    (b"te", 1.20),
+    (b"of", 1.17),
+    (b"ed", 1.17),
+    (b"is", 1.13),
+    (b"it", 1.12),
+    (b"al", 1.09),
+    (b"ar", 1.07),
+    (b"st", 1.05),
+    (b"to", 1.04),
+    (b"nt", 1.04),
+    (b"ng", 0.95),
+    (b"se", 0.93),
+    (b"ha", 0.93),
+    (b"as", 0.87),
+    (b"ou", 0.87),
+    (b"io", 0.83),
+    (b"le", 0.83),
+    (b"ve", 0.83),
+    (b"co", 0.79),
+    (b"me", 0.79),
+    (b"de", 0.76),
+    (b"hi", 0.76),
+    (b"ri", 0.73),
+    (b"ro", 0.73),
+    (b"ic", 0.70),
+    (b"ne", 0.69),
+    (b"ea", 0.69),
+    (b"ra", 0.69),
+    (b"ce", 0.65),
+    (b"li", 0.62),
+    (b"ch", 0.60),
+    (b"ll", 0.58),
+    (b"be", 0.58),
+    (b"ma", 0.57),
+    (b"si", 0.55),
+    (b"om", 0.55),
+    (b"ur", 0.54),
+];
+
+// Frequency assumed for letter bigrams not in the table, in percent
+const BIGRAM_FLOOR: f64 = 0.05;
+
+pub fn is_printable(x: u8) -> bool {
+    matches!(x, b'\n' | b'\r' | b'\t' | 0x20..=0x7e)
+}
+
+pub fn count_non_printable(plaintext: &[u8]) -> usize {
+    plaintext.iter().filter(|&&x| !is_printable(x)).count()
+}
+
+// Counts the bytes in the whitelist, this is the original scorer and is
+// kept as a baseline.
+pub struct WhitelistScorer;
+
+impl Scorer for WhitelistScorer {
+    fn score(&self, plaintext: &[u8]) -> f64 {
+        plaintext
+            .iter()
+            .map(|&x| match x {
+                10 => 1,       // '\n'
+                32 => 1,       // ' '
+                39 => 1,       // '\''
+                45 => 1,       // -
+                48..=57 => 1,  // 0-9
+                58 => 1,       // :
+                65..=90 => 1,  // A-Z
+                97..=122 => 1, // a-z
+                _ => 0,
+            })
+            .sum::<u32>() as f64
+    }
+}
+
+// Negated chi-squared statistic of the observed character distribution
+// against English, per character. Non-printable bytes are not counted in
+// any category, so they only push the statistic up.
+pub struct ChiSquaredScorer;
+
+impl ChiSquaredScorer {
+    pub fn chi_squared(plaintext: &[u8]) -> f64 {
+        if plaintext.is_empty() {
+            return 0.0;
+        }
+
+        let mut letters = [0usize; 26];
+        let mut spaces = 0;
+        let mut other = 0;
+        for &x in plaintext {
+            match x {
+                b'a'..=b'z' => letters[(x - b'a') as usize] += 1,
+                b'A'..=b'Z' => letters[(x - b'A') as usize] += 1,
+                b' ' => spaces += 1,
+                _ if is_printable(x) => other += 1,
+                _ => {}
+            }
+        }
+
+        let len = plaintext.len() as f64;
+        let term = |observed: usize, share: f64| {
+            let expected = share * len;
+            let diff = observed as f64 - expected;
+            diff * diff / expected
+        };
+
+        let letter_total: f64 = LETTER_FREQUENCIES.iter().sum();
+        let letters_chi: f64 = letters
+            .iter()
+            .zip(LETTER_FREQUENCIES.iter())
+            .map(|(&observed, &freq)| term(observed, LETTER_SHARE * freq / letter_total))
+            .sum();
+
+        letters_chi + term(spaces, SPACE_SHARE) + term(other, OTHER_SHARE)
+    }
+}
+
+impl Scorer for ChiSquaredScorer {
+    fn score(&self, plaintext: &[u8]) -> f64 {
+        if plaintext.is_empty() {
+            return 0.0;
+        }
+        -ChiSquaredScorer::chi_squared(plaintext) / plaintext.len() as f64
+    }
+}
+
+// Log-likelihood of the letter bigrams against a uniform distribution, per
+// character. Pairs that are not two letters do not count.
+pub struct BigramScorer;
+
+impl BigramScorer {
+    fn log_likelihood(pair: &[u8]) -> Option<f64> {
+        if !pair[0].is_ascii_alphabetic() || !pair[1].is_ascii_alphabetic() {
+            return None;
+        }
+        let lower = [pair[0].to_ascii_lowercase(), pair[1].to_ascii_lowercase()];
+        let freq = BIGRAM_FREQUENCIES
+            .iter()
+            .find(|(bigram, _)| **bigram == lower)
+            .map(|&(_, freq)| freq)
+            .unwrap_or(BIGRAM_FLOOR);
+
+        let uniform = 100.0 / (26.0 * 26.0);
+        Some((freq / uniform).ln())
+    }
+}
+
+impl Scorer for BigramScorer {
+    fn score(&self, plaintext: &[u8]) -> f64 {
+        if plaintext.is_empty() {
+            return 0.0;
+        }
+        let total: f64 = plaintext
+            .windows(2)
+            .filter_map(BigramScorer::log_likelihood)
+            .sum();
+        total / plaintext.len() as f64
+    }
+}
+
+// Weighted combination of chi-squared, bigrams and a penalty for every
+// non-printable byte. This is the scorer to use unless there is a reason
+// not to.
+pub struct EnglishScorer {
+    pub chi_squared_weight: f64,
+    pub bigram_weight: f64,
+    pub non_printable_penalty: f64,
+}
+
+impl Default for EnglishScorer {
+    fn default() -> Self {
+        EnglishScorer {
+            chi_squared_weight: 1.0,
+            bigram_weight: 1.0,
+            non_printable_penalty: 10.0,
+        }
+    }
+}
+
+impl Scorer for EnglishScorer {
+    fn score(&self, plaintext: &[u8]) -> f64 {
+        if plaintext.is_empty() {
+            return 0.0;
+        }
+        let non_printable = count_non_printable(plaintext) as f64 / plaintext.len() as f64;
+
+        self.chi_squared_weight * ChiSquaredScorer.score(plaintext)
+            + self.bigram_weight * BigramScorer.score(plaintext)
+            - self.non_printable_penalty * non_printable
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENGLISH: &[u8] = b"Now that the party is jumping, with the bass kicked in";
+    const NOISE: &[u8] = b"xq#zj%kv^wq@zz!pxj~vqk*zjw$qx&zkv+jqz=wxk?qzv|jxq<zk";
+
+    #[test]
+    fn test_scorers_prefer_english() {
+        assert!(WhitelistScorer.score(ENGLISH) > WhitelistScorer.score(b"\x00\x01\x02"));
+        assert!(ChiSquaredScorer.score(ENGLISH) > ChiSquaredScorer.score(NOISE));
+        assert!(BigramScorer.score(ENGLISH) > BigramScorer.score(NOISE));
+        assert!(EnglishScorer::default().score(ENGLISH) > EnglishScorer::default().score(NOISE));
+    }
+
+    #[test]
+    fn test_english_scorer_penalizes_non_printable() {
+        let scorer = EnglishScorer::default();
+        let mut damaged = ENGLISH.to_vec();
+        damaged[3] = 0x00;
+        damaged[10] = 0x9f;
+
+        assert_eq!(2, count_non_printable(&damaged));
+        assert!(scorer.score(ENGLISH) > scorer.score(&damaged));
+    }
+
+    #[test]
+    fn test_scorers_empty_input() {
+        assert_eq!(0.0, ChiSquaredScorer.score(b""));
+        assert_eq!(0.0, BigramScorer.score(b""));
+        assert_eq!(0.0, EnglishScorer::default().score(b""));
+    }
+}
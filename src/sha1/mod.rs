@@ -0,0 +1,197 @@
+// SHA-1 as specified in FIPS 180-4. The state and the length counter can be
+// set from the outside, which is what length extension needs.
+
+use crate::error::Result;
+use crate::hash::{self, MdHash};
+
+pub const BLOCK_SIZE: usize = 64;
+pub const DIGEST_SIZE: usize = 20;
+
+const INITIAL_STATE: [u32; 5] = [
+    0x6745_2301,
+    0xefcd_ab89,
+    0x98ba_dcfe,
+    0x1032_5476,
+    0xc3d2_e1f0,
+];
+
+#[derive(Clone)]
+pub struct Sha1 {
+    state: [u32; 5],
+    buffer: Vec<u8>,
+    // Bytes processed so far, including any counted by from_state
+    length: u64,
+}
+
+impl Default for Sha1 {
+    fn default() -> Self {
+        Sha1::new()
+    }
+}
+
+impl Sha1 {
+    // Continues from `state` as if `length` bytes (a multiple of the block
+    // size, padding included) had already been hashed.
+    pub fn from_state(state: [u32; 5], length: u64) -> Sha1 {
+        Sha1 {
+            state,
+            buffer: Vec::with_capacity(BLOCK_SIZE),
+            length,
+        }
+    }
+
+    // The state a digest was produced from, for use with from_state.
+    pub fn state_from_digest(digest: &[u8]) -> Result<[u32; 5]> {
+        let mut state = [0; 5];
+        hash::state_from_digest::<Sha1>(digest, &mut state)?;
+        Ok(state)
+    }
+}
+
+impl MdHash for Sha1 {
+    const BLOCK_SIZE: usize = BLOCK_SIZE;
+    const DIGEST_SIZE: usize = DIGEST_SIZE;
+    const BIG_ENDIAN: bool = true;
+
+    fn new() -> Sha1 {
+        Sha1::from_state(INITIAL_STATE, 0)
+    }
+
+    fn from_digest(digest: &[u8], length: u64) -> Result<Sha1> {
+        Ok(Sha1::from_state(Sha1::state_from_digest(digest)?, length))
+    }
+
+    fn compress(&mut self, block: &[u8]) {
+        let mut w = [0u32; 80];
+        for (i, chunk) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = self.state;
+
+        for (i, &word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5a82_7999),
+                20..=39 => (b ^ c ^ d, 0x6ed9_eba1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8f1b_bcdc),
+                _ => (b ^ c ^ d, 0xca62_c1d6),
+            };
+
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        for (word, value) in self.state.iter_mut().zip([a, b, c, d, e].iter()) {
+            *word = word.wrapping_add(*value);
+        }
+    }
+
+    fn buffer_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.buffer
+    }
+
+    fn length_mut(&mut self) -> &mut u64 {
+        &mut self.length
+    }
+
+    fn state(&self) -> &[u32] {
+        &self.state
+    }
+}
+
+// The padding SHA-1 appends to a message of `message_len` bytes.
+pub fn md_padding(message_len: u64) -> Vec<u8> {
+    <Sha1 as MdHash>::md_padding(message_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use crate::utils::encode_hex;
+
+    #[test]
+    fn test_sha1_nist_vectors() {
+        // FIPS 180 examples and the NIST "long" vectors
+        assert_eq!(
+            "da39a3ee5e6b4b0d3255bfef95601890afd80709",
+            encode_hex(&Sha1::digest(b""))
+        );
+        assert_eq!(
+            "a9993e364706816aba3e25717850c26c9cd0d89d",
+            encode_hex(&Sha1::digest(b"abc"))
+        );
+        assert_eq!(
+            "84983e441c3bd26ebaae4aa1f95129e5e54670f1",
+            encode_hex(&Sha1::digest(
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+            ))
+        );
+        assert_eq!(
+            "34aa973cd4c4daa4f61eeb2bdbad27316534016f",
+            encode_hex(&Sha1::digest(&vec![b'a'; 1_000_000]))
+        );
+    }
+
+    #[test]
+    fn test_sha1_incremental_update() {
+        let data: Vec<u8> = (0..200).collect();
+
+        let mut sha1 = Sha1::new();
+        for chunk in data.chunks(7) {
+            sha1.update(chunk);
+        }
+
+        assert_eq!(Sha1::digest(&data), sha1.finalize());
+    }
+
+    #[test]
+    fn test_md_padding() {
+        for len in 0..200 {
+            let padding = md_padding(len);
+            assert_eq!(0, (len as usize + padding.len()) % BLOCK_SIZE);
+            assert!(padding.len() > 8 && padding.len() <= BLOCK_SIZE + 8);
+            assert_eq!((len * 8).to_be_bytes(), padding[padding.len() - 8..]);
+        }
+    }
+
+    #[test]
+    fn test_sha1_from_state() {
+        let message = b"abc";
+        let mut glued = message.to_vec();
+        glued.extend(md_padding(message.len() as u64));
+        glued.extend_from_slice(b"def");
+
+        let state = Sha1::state_from_digest(&Sha1::digest(message)).unwrap();
+        let mut sha1 = Sha1::from_state(state, BLOCK_SIZE as u64);
+        sha1.update(b"def");
+
+        assert_eq!(Sha1::digest(&glued), sha1.finalize());
+    }
+
+    #[test]
+    fn test_state_from_digest_wrong_length() {
+        let digest = Sha1::digest(b"abc");
+
+        assert!(matches!(
+            Sha1::state_from_digest(&digest[..19]),
+            Err(Error::InvalidDataLength(19))
+        ));
+        assert!(matches!(
+            Sha1::from_digest(&[digest, vec![0; 4]].concat(), 64),
+            Err(Error::InvalidDataLength(24))
+        ));
+    }
+}
@@ -0,0 +1,43 @@
+use std::env;
+use std::process;
+use std::time::Duration;
+
+use challenge::random::random_key;
+use challenge::timing_leak::{http_probe, recover_signature, request, HmacServer, Statistic};
+use challenge::utils::encode_hex;
+
+fn usage() {
+    println!("Usage: timing_attack <file> <delay in ms> <samples> [median|trimmed]");
+    std::process::exit(-1);
+}
+
+pub fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    if args.len() != 4 && args.len() != 5 {
+        usage();
+        process::exit(1);
+    }
+
+    let file = &args[1];
+    let delay: u64 = args[2].parse().expect("Invalid delay");
+    let samples: usize = args[3].parse().expect("Invalid samples");
+    let statistic = match args.get(4).map(|s| s.as_str()) {
+        None | Some("median") => Statistic::Median,
+        Some("trimmed") => Statistic::TrimmedMean(20),
+        Some(_) => {
+            usage();
+            process::exit(1);
+        }
+    };
+
+    let server = HmacServer::start(&random_key(16), Duration::from_millis(delay), 0)
+        .expect("Unable to start server");
+    println!("Server listening on {}", server.addr());
+
+    let signature = recover_signature(http_probe(server.addr(), file), 20, samples, statistic);
+    println!("Signature: {}", encode_hex(&signature));
+
+    let valid = request(server.addr(), file, &signature).expect("Request failed");
+    println!("Valid: {}", valid);
+}
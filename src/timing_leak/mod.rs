@@ -0,0 +1,395 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::cookie;
+use crate::error::{Error, Result};
+use crate::mac::hmac;
+use crate::sha1::Sha1;
+use crate::utils::{decode_hex, encode_hex};
+
+// Compares byte by byte, sleeping after every byte that matches and
+// returning as soon as one does not. How long it takes tells how many
+// leading bytes were right.
+pub fn insecure_compare(a: &[u8], b: &[u8], delay: Duration) -> bool {
+    insecure_compare_with(a, b, || thread::sleep(delay))
+}
+
+// insecure_compare with the sleep passed in, so tests can count the delays
+// instead of timing them.
+pub fn insecure_compare_with<F: FnMut()>(a: &[u8], b: &[u8], mut sleep: F) -> bool {
+    for (x, y) in a.iter().zip(b.iter()) {
+        if x != y {
+            return false;
+        }
+        sleep();
+    }
+
+    a.len() == b.len()
+}
+
+// The challenge 31 web application: answers
+// GET /test?file=<name>&signature=<hex> with 200 if the signature is the
+// HMAC-SHA1 of the file name, and 500 otherwise, checking it with
+// insecure_compare.
+//
+// The server runs on a thread of its own on 127.0.0.1, handling one
+// request at a time, until it is dropped.
+pub struct HmacServer {
+    addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl HmacServer {
+    // Binds to the port, or any free port if it is 0.
+    pub fn start(key: &[u8], delay: Duration, port: u16) -> Result<HmacServer> {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        let addr = listener.local_addr()?;
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let key = key.to_vec();
+        let stop_flag = stop.clone();
+        let handle = thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stop_flag.load(Ordering::SeqCst) {
+                    break;
+                }
+                if let Ok(stream) = stream {
+                    // A client going away is not the server's problem
+                    let _ = handle_request(stream, &key, delay);
+                }
+            }
+        });
+
+        Ok(HmacServer {
+            addr,
+            stop,
+            handle: Some(handle),
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for HmacServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        // Wake up the accept loop so it sees the flag
+        let _ = TcpStream::connect(self.addr);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn handle_request(mut stream: TcpStream, key: &[u8], delay: Duration) -> Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Read the headers, so the client is done writing when the response
+    // comes
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim_end().is_empty() {
+        header.clear();
+    }
+
+    let status = match check_request(&request_line, key, delay) {
+        Ok(true) => "200 OK",
+        Ok(false) | Err(_) => "500 Internal Server Error",
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        status
+    )?;
+    Ok(())
+}
+
+fn check_request(request_line: &str, key: &[u8], delay: Duration) -> Result<bool> {
+    let target = request_line
+        .split_whitespace()
+        .nth(1)
+        .ok_or_else(|| Error::MalformedQueryString(request_line.to_string()))?;
+    let query = match target.split_once('?') {
+        Some(("/test", query)) => query,
+        _ => return Ok(false),
+    };
+
+    let items = cookie::parse_querystring(query)?;
+    let file = cookie::lookup(&items, "file").unwrap_or("");
+    let signature = decode_hex(cookie::lookup(&items, "signature").unwrap_or(""))?;
+
+    Ok(insecure_compare(
+        &hmac::<Sha1>(key, file.as_bytes()),
+        &signature,
+        delay,
+    ))
+}
+
+// Asks the server at `addr` whether the signature is valid for the file.
+pub fn request(addr: SocketAddr, file: &str, signature: &[u8]) -> Result<bool> {
+    let mut stream = TcpStream::connect(addr)?;
+    let request = format!(
+        "GET /test?file={}&signature={} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+        file,
+        encode_hex(signature),
+        addr
+    );
+    stream.write_all(request.as_bytes())?;
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+
+    Ok(response.starts_with("HTTP/1.1 200"))
+}
+
+// Times a request to the server, for recover_signature.
+pub fn http_probe(addr: SocketAddr, file: &str) -> impl FnMut(&[u8]) -> Duration + '_ {
+    move |signature| {
+        let start = Instant::now();
+        // A failed request just gives a useless sample
+        let _ = request(addr, file, signature);
+        start.elapsed()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Statistic {
+    Median,
+    // Mean after dropping the given percentage of samples at each end
+    TrimmedMean(usize),
+}
+
+impl Statistic {
+    pub fn apply(self, samples: &mut [Duration]) -> Duration {
+        if samples.is_empty() {
+            return Duration::from_secs(0);
+        }
+        samples.sort();
+
+        match self {
+            // The lower median, as hiccups only ever make samples slower
+            Statistic::Median => samples[(samples.len() - 1) / 2],
+            Statistic::TrimmedMean(percent) => {
+                let trim = (samples.len() * percent.min(49) / 100).min((samples.len() - 1) / 2);
+                let kept = &samples[trim..samples.len() - trim];
+                kept.iter().sum::<Duration>() / kept.len() as u32
+            }
+        }
+    }
+}
+
+// Candidates timed again before picking one, and how many times more
+// samples they get.
+const FINALISTS: usize = 8;
+const FINALIST_SAMPLES_FACTOR: usize = 4;
+
+// Times every candidate for byte `i` of the signature `rounds` times, taking
+// turns so that any drift in the timing hits all candidates alike.
+fn time_candidates<F>(
+    probe: &mut F,
+    signature: &mut [u8],
+    i: usize,
+    candidates: &[u8],
+    timings: &mut [Vec<Duration>],
+    rounds: usize,
+) where
+    F: FnMut(&[u8]) -> Duration,
+{
+    for _ in 0..rounds {
+        for &candidate in candidates {
+            signature[i] = candidate;
+            timings[candidate as usize].push(probe(signature));
+        }
+    }
+}
+
+// Candidates sorted by their statistic, slowest first.
+fn rank_candidates(candidates: &[u8], timings: &[Vec<Duration>], statistic: Statistic) -> Vec<u8> {
+    let mut ranked: Vec<(Duration, u8)> = candidates
+        .iter()
+        .map(|&candidate| {
+            let mut samples = timings[candidate as usize].clone();
+            (statistic.apply(&mut samples), candidate)
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.cmp(a));
+
+    ranked.into_iter().map(|(_, candidate)| candidate).collect()
+}
+
+// Recovers a signature one byte at a time from how long the probe takes.
+//
+// Every candidate for a byte is timed `samples` times and the slowest
+// few are timed again with more samples, so a single hiccup cannot decide
+// the byte. The median or a trimmed mean keeps the remaining hiccups from
+// mattering when the leak is only a few milliseconds per byte.
+pub fn recover_signature<F>(
+    mut probe: F,
+    signature_len: usize,
+    samples: usize,
+    statistic: Statistic,
+) -> Vec<u8>
+where
+    F: FnMut(&[u8]) -> Duration,
+{
+    let all: Vec<u8> = (0..=255).collect();
+    let mut signature = vec![0; signature_len];
+
+    for i in 0..signature_len {
+        let mut timings = vec![Vec::new(); 256];
+        time_candidates(&mut probe, &mut signature, i, &all, &mut timings, samples);
+
+        let mut finalists = rank_candidates(&all, &timings, statistic);
+        finalists.truncate(FINALISTS);
+        time_candidates(
+            &mut probe,
+            &mut signature,
+            i,
+            &finalists,
+            &mut timings,
+            samples * FINALIST_SAMPLES_FACTOR,
+        );
+
+        signature[i] = rank_candidates(&finalists, &timings, statistic)[0];
+    }
+
+    signature
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::{random_key, random_usize};
+
+    #[test]
+    fn test_insecure_compare() {
+        let delay = Duration::from_millis(0);
+        assert!(insecure_compare(b"abc", b"abc", delay));
+        assert!(!insecure_compare(b"abc", b"abd", delay));
+        assert!(!insecure_compare(b"abc", b"ab", delay));
+        assert!(!insecure_compare(b"", b"a", delay));
+    }
+
+    #[test]
+    fn test_insecure_compare_leaks_timing() {
+        let delays = |a: &[u8], b: &[u8]| {
+            let mut count = 0;
+            insecure_compare_with(a, b, || count += 1);
+            count
+        };
+
+        assert_eq!(0, delays(b"abcd", b"xbcd"));
+        assert_eq!(3, delays(b"abcd", b"abcx"));
+        assert_eq!(4, delays(b"abcd", b"abcd"));
+    }
+
+    #[test]
+    fn test_hmac_server() {
+        let key = b"YELLOW SUBMARINE";
+        let server = HmacServer::start(key, Duration::from_millis(0), 0).unwrap();
+
+        let signature = hmac::<Sha1>(key, b"foo");
+        assert!(request(server.addr(), "foo", &signature).unwrap());
+        assert!(!request(server.addr(), "bar", &signature).unwrap());
+        assert!(!request(server.addr(), "foo", &signature[..19]).unwrap());
+    }
+
+    #[test]
+    fn test_http_probe() {
+        let key = b"YELLOW SUBMARINE";
+        let server = HmacServer::start(key, Duration::from_millis(0), 0).unwrap();
+        let signature = hmac::<Sha1>(key, b"foo");
+        let mut wrong = signature.clone();
+        wrong[0] ^= 1;
+
+        // One probe each, which has to wait for the whole response
+        let mut probe = http_probe(server.addr(), "foo");
+        probe(&wrong);
+        probe(&signature);
+
+        assert!(request(server.addr(), "foo", &signature).unwrap());
+        assert!(!request(server.addr(), "foo", &wrong).unwrap());
+
+        let mut stream = TcpStream::connect(server.addr()).unwrap();
+        stream
+            .write_all(b"GET /other HTTP/1.1\r\nConnection: close\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 500 Internal Server Error\r\n"));
+    }
+
+    #[test]
+    fn test_statistic() {
+        let mut samples: Vec<Duration> = [5, 1, 4, 2, 3, 100]
+            .iter()
+            .map(|&ms| Duration::from_millis(ms))
+            .collect();
+
+        assert_eq!(
+            Duration::from_millis(3),
+            Statistic::Median.apply(&mut samples)
+        );
+        assert_eq!(
+            Duration::from_micros(3500),
+            Statistic::TrimmedMean(20).apply(&mut samples)
+        );
+        assert_eq!(Duration::from_secs(0), Statistic::Median.apply(&mut []));
+    }
+
+    // Simulates insecure_compare with a 3ms leak per byte, uniform noise of
+    // up to 5ms and the odd 50ms hiccup, without sleeping.
+    fn synthetic_probe(secret: &[u8]) -> impl FnMut(&[u8]) -> Duration + '_ {
+        move |signature| {
+            let matching = secret
+                .iter()
+                .zip(signature.iter())
+                .take_while(|(a, b)| a == b)
+                .count();
+            let mut micros = 1000 + 3000 * matching + random_usize(0, 5000);
+            if random_usize(0, 50) == 0 {
+                micros += 50_000;
+            }
+            Duration::from_micros(micros as u64)
+        }
+    }
+
+    #[test]
+    fn test_recover_signature_median() {
+        let secret = random_key(8);
+        let recovered = recover_signature(synthetic_probe(&secret), 8, 31, Statistic::Median);
+
+        assert_eq!(secret, recovered);
+    }
+
+    #[test]
+    fn test_recover_signature_trimmed_mean() {
+        let secret = random_key(8);
+        let recovered =
+            recover_signature(synthetic_probe(&secret), 8, 31, Statistic::TrimmedMean(20));
+
+        assert_eq!(secret, recovered);
+    }
+
+    #[test]
+    #[ignore = "times a real server, which is unreliable on a loaded machine"]
+    fn test_recover_signature_http() {
+        // Only the first byte, as every right byte makes the server slower
+        let key = b"YELLOW SUBMARINE";
+        let server = HmacServer::start(key, Duration::from_millis(5), 0).unwrap();
+        let expected = hmac::<Sha1>(key, b"foo");
+
+        let recovered =
+            recover_signature(http_probe(server.addr(), "foo"), 1, 3, Statistic::Median);
+
+        assert_eq!(expected[0], recovered[0]);
+    }
+}
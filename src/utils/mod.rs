@@ -3,10 +3,14 @@ use std::fs::File;
 use std::io::BufRead;
 use std::io::BufReader;
 
-pub fn decode_hex(hex_str: &str) -> Option<Vec<u8>> {
+use crate::error::{Error, Result};
+
+pub fn decode_hex(hex_str: &str) -> Result<Vec<u8>> {
     let len = hex_str.len();
 
-    assert_eq!(len % 2, 0);
+    if !len.is_multiple_of(2) {
+        return Err(Error::InvalidHex);
+    }
 
     let mut v: Vec<u8> = Vec::with_capacity(len / 2);
 
@@ -20,11 +24,11 @@ pub fn decode_hex(hex_str: &str) -> Option<Vec<u8>> {
                     v.push((prev << 4) | x as u8);
                 }
             }
-            None => return None,
+            None => return Err(Error::InvalidHex),
         }
     }
 
-    Some(v)
+    Ok(v)
 }
 
 const HEX_MAP: [char; 16] = [
@@ -32,7 +36,6 @@ const HEX_MAP: [char; 16] = [
 ];
 
 pub fn encode_hex(bytes: &[u8]) -> String {
-    assert!(!bytes.is_empty());
     let mut out = Vec::with_capacity(bytes.len() * 2);
     for b in bytes.iter() {
         out.push(HEX_MAP[((b & 0xF0) >> 4) as usize] as u8);
@@ -74,7 +77,7 @@ pub fn encode_b64(bytes: &[u8]) -> String {
             write(B64_MAP[((n >> 18) & 0x3F) as usize] as u8);
             write(B64_MAP[((n >> 12) & 0x3F) as usize] as u8);
             write(B64_MAP[((n >> 6) & 0x3F) as usize] as u8);
-            write(B64_MAP[((n >> 0) & 0x3F) as usize] as u8);
+            write(B64_MAP[(n & 0x3F) as usize] as u8);
         }
 
         match mod_len {
@@ -97,21 +100,27 @@ pub fn encode_b64(bytes: &[u8]) -> String {
     unsafe { String::from_utf8_unchecked(out) }
 }
 
-fn b64_char_to_u8(c: char) -> u8 {
+fn b64_char_to_u8(c: char) -> Result<u8> {
+    if !c.is_ascii() {
+        return Err(Error::InvalidBase64);
+    }
     let d = c as u8;
     match d {
-        65..=90 => d - b'A',          // A-Z
-        97..=122 => d - b'a' + 26,    // a-z
-        48..=57 => d - b'0' + 2 * 26, // 0-9
-        43 => 62,                     // +
-        47 => 63,                     // /
-        61 => 0xFF,                   // =
-        _ => panic!("Invalid b64 char '{:?}'", d),
+        65..=90 => Ok(d - b'A'),          // A-Z
+        97..=122 => Ok(d - b'a' + 26),    // a-z
+        48..=57 => Ok(d - b'0' + 2 * 26), // 0-9
+        43 => Ok(62),                     // +
+        47 => Ok(63),                     // /
+        61 => Ok(0xFF),                   // =
+        _ => Err(Error::InvalidBase64),
     }
 }
 
-pub fn decode_b64(b64_str: &str) -> Vec<u8> {
-    assert!(b64_str.len() % 4 == 0, "b64_str need to be a multiple of 4");
+pub fn decode_b64(b64_str: &str) -> Result<Vec<u8>> {
+    // Lengths are in bytes, so a multi-byte char would throw off the grouping
+    if !b64_str.is_ascii() || !b64_str.len().is_multiple_of(4) {
+        return Err(Error::InvalidBase64);
+    }
 
     let out_len = 3 * b64_str.len() / 4;
     let mut out: Vec<u8> = Vec::with_capacity(out_len);
@@ -123,10 +132,19 @@ pub fn decode_b64(b64_str: &str) -> Vec<u8> {
             (s_in.next(), s_in.next(), s_in.next(), s_in.next())
         {
             // 4 bytes - > 3 bytes
-            let c1 = b64_char_to_u8(first);
-            let c2 = b64_char_to_u8(second);
-            let c3 = b64_char_to_u8(third);
-            let c4 = b64_char_to_u8(fourth);
+            let c1 = b64_char_to_u8(first)?;
+            let c2 = b64_char_to_u8(second)?;
+            let c3 = b64_char_to_u8(third)?;
+            let c4 = b64_char_to_u8(fourth)?;
+
+            // Padding is only allowed at the end, and never in the first two positions
+            if c1 == 0xFF
+                || c2 == 0xFF
+                || (c3 == 0xFF && c4 != 0xFF)
+                || (c4 == 0xFF && !s_in.as_str().is_empty())
+            {
+                return Err(Error::InvalidBase64);
+            }
 
             out.push(c1 << 2 | (c2 & 0x30) >> 4);
 
@@ -140,33 +158,33 @@ pub fn decode_b64(b64_str: &str) -> Vec<u8> {
         }
     }
 
-    out
+    Ok(out)
 }
 
-pub fn load_file(file_path: &str) -> String {
+pub fn load_file(file_path: &str) -> Result<String> {
     let mut content = String::new();
-    let f = File::open(file_path).expect("Unable to open file");
+    let f = File::open(file_path)?;
     let br = BufReader::new(f);
     for line in br.lines() {
-        let l = line.unwrap();
+        let l = line?;
         content.push_str(&l);
     }
-    content
+    Ok(content)
 }
 
-pub fn load_file_per_line(file_path: &str) -> Vec<String> {
+pub fn load_file_per_line(file_path: &str) -> Result<Vec<String>> {
     let mut lines = Vec::new();
-    let f = File::open(file_path).expect("Unable to open file");
+    let f = File::open(file_path)?;
     let br = BufReader::new(f);
     for line in br.lines() {
-        let l = line.unwrap();
+        let l = line?;
         lines.push(l);
     }
-    lines
+    Ok(lines)
 }
 
 fn pad_length(data_len: usize, block_size: usize) -> usize {
-    if data_len % block_size == 0 {
+    if data_len.is_multiple_of(block_size) {
         block_size
     } else {
         (block_size - (data_len % block_size)) % block_size
@@ -182,24 +200,27 @@ pub fn pkcs_7_padding(buf: &[u8], block_size: usize) -> Vec<u8> {
     output
 }
 
-pub fn pkcs_7_padding_validate(buf: &[u8], block_size: usize) -> Option<Vec<u8>> {
-    let pad_char = buf[buf.len() - 1];
+pub fn pkcs_7_padding_validate(buf: &[u8], block_size: usize) -> Result<Vec<u8>> {
+    let pad_char = match buf.last() {
+        Some(&c) => c,
+        None => return Err(Error::BadPadding),
+    };
 
     let pad_length = pad_char as usize;
 
-    if pad_length > block_size {
-        return None;
+    if pad_length == 0 || pad_length > block_size || pad_length > buf.len() {
+        return Err(Error::BadPadding);
     }
 
     let padding = &buf[buf.len() - pad_length..buf.len()];
 
     for char in padding {
         if char != &pad_char {
-            return None;
+            return Err(Error::BadPadding);
         }
     }
 
-    Some(buf[0..buf.len() - pad_length].to_vec())
+    Ok(buf[0..buf.len() - pad_length].to_vec())
 }
 
 pub fn count_duplicate_blocks(bytes: &[u8], block_size: usize) -> (u32, u32) {
@@ -230,27 +251,33 @@ mod tests {
 
     #[test]
     fn test_decode_hex_1() {
-        let res: Option<Vec<u8>> = Some(vec![255]);
-        assert_eq!(res, decode_hex("FF"));
-        assert_eq!(res, decode_hex("ff"));
+        let res: Vec<u8> = vec![255];
+        assert_eq!(res, decode_hex("FF").unwrap());
+        assert_eq!(res, decode_hex("ff").unwrap());
     }
 
     #[test]
     fn test_decode_hex_2() {
         let res: Vec<u8> = vec![0];
-        assert_eq!(Some(res), decode_hex("00"));
+        assert_eq!(res, decode_hex("00").unwrap());
     }
 
     #[test]
     fn test_decode_hex_3() {
         let res: Vec<u8> = vec![0, 255];
-        assert_eq!(Some(res), decode_hex("00FF"));
+        assert_eq!(res, decode_hex("00FF").unwrap());
     }
 
     #[test]
     fn test_decode_hex_4() {
         let res: Vec<u8> = vec![222, 173, 190, 239];
-        assert_eq!(Some(res), decode_hex("DEADBEEF"));
+        assert_eq!(res, decode_hex("DEADBEEF").unwrap());
+    }
+
+    #[test]
+    fn test_decode_hex_invalid() {
+        assert!(decode_hex("abc").is_err());
+        assert!(decode_hex("zz").is_err());
     }
 
     #[test]
@@ -289,30 +316,30 @@ mod tests {
 
     #[test]
     fn test_b64_char_to_u8() {
-        assert_eq!(0, super::b64_char_to_u8('A'));
-        assert_eq!(25, super::b64_char_to_u8('Z'));
-        assert_eq!(26, super::b64_char_to_u8('a'));
-        assert_eq!(51, super::b64_char_to_u8('z'));
-        assert_eq!(52, super::b64_char_to_u8('0'));
-        assert_eq!(61, super::b64_char_to_u8('9'));
-        assert_eq!(62, super::b64_char_to_u8('+'));
-        assert_eq!(63, super::b64_char_to_u8('/'));
-        assert_eq!(0xFF, super::b64_char_to_u8('='));
+        assert_eq!(0, super::b64_char_to_u8('A').unwrap());
+        assert_eq!(25, super::b64_char_to_u8('Z').unwrap());
+        assert_eq!(26, super::b64_char_to_u8('a').unwrap());
+        assert_eq!(51, super::b64_char_to_u8('z').unwrap());
+        assert_eq!(52, super::b64_char_to_u8('0').unwrap());
+        assert_eq!(61, super::b64_char_to_u8('9').unwrap());
+        assert_eq!(62, super::b64_char_to_u8('+').unwrap());
+        assert_eq!(63, super::b64_char_to_u8('/').unwrap());
+        assert_eq!(0xFF, super::b64_char_to_u8('=').unwrap());
     }
 
     #[test]
     fn test_decode_b64_1() {
-        assert_eq!(vec![0, 0, 255], decode_b64("AAD/"));
+        assert_eq!(vec![0, 0, 255], decode_b64("AAD/").unwrap());
     }
 
     #[test]
     fn test_decode_b64_2() {
-        assert_eq!(vec![222, 173, 190, 239], decode_b64("3q2+7w=="));
+        assert_eq!(vec![222, 173, 190, 239], decode_b64("3q2+7w==").unwrap());
     }
 
     #[test]
     fn test_decode_b64_3() {
-        assert_eq!(vec![255, 255], decode_b64("//8="));
+        assert_eq!(vec![255, 255], decode_b64("//8=").unwrap());
     }
 
     #[test]
@@ -322,9 +349,20 @@ mod tests {
         assert_eq!(
             output,
             decode_b64("SSdtIGtpbGxpbmcgeW91ciBicmFpbiBsaWtlIGEgcG9pc29ub3VzIG11c2hyb29t",)
+                .unwrap()
         );
     }
 
+    #[test]
+    fn test_decode_b64_invalid() {
+        assert!(decode_b64("AAD").is_err());
+        assert!(decode_b64("AA*/").is_err());
+        assert!(decode_b64("A=AA").is_err());
+        assert!(decode_b64("AA==AAAA").is_err());
+        assert!(decode_b64("AAé").is_err());
+        assert!(decode_b64("éé").is_err());
+    }
+
     #[test]
     fn test_pad_length() {
         assert_eq!(16, pad_length(16, 16));
@@ -365,18 +403,25 @@ mod tests {
     #[test]
     fn test_pkcs_7_validate_2_invalid() {
         let input = b"ICE ICE BABY\x05\x05\x05\x05".to_vec();
-        assert!(pkcs_7_padding_validate(&input, 16).is_none());
+        assert!(pkcs_7_padding_validate(&input, 16).is_err());
     }
 
     #[test]
     fn test_pkcs_7_validate_3_invalid() {
         let input = b"ICE ICE BABY\x01\x02\x03\x04".to_vec();
-        assert!(pkcs_7_padding_validate(&input, 16).is_none());
+        assert!(pkcs_7_padding_validate(&input, 16).is_err());
     }
 
     #[test]
     fn test_pkcs_7_validate_4_invalid() {
         let input = b"ICE ICE BABY\x01\x02\x03\x40".to_vec();
-        assert!(pkcs_7_padding_validate(&input, 16).is_none());
+        assert!(pkcs_7_padding_validate(&input, 16).is_err());
+    }
+
+    #[test]
+    fn test_pkcs_7_validate_5_invalid() {
+        assert!(pkcs_7_padding_validate(b"", 16).is_err());
+        assert!(pkcs_7_padding_validate(b"ICE ICE BABY\x00", 16).is_err());
+        assert!(pkcs_7_padding_validate(b"\x03\x03", 16).is_err());
     }
 }
@@ -1,5 +1,9 @@
+use std::cmp::Ordering;
+
 use itertools::Itertools;
 
+use crate::scorer::Scorer;
+
 pub fn xor(a: &[u8], b: &[u8]) -> Vec<u8> {
     assert_eq!(a.len(), b.len());
 
@@ -17,63 +21,50 @@ pub fn repeating_key(key: &[u8], length: usize) -> Vec<u8> {
     out
 }
 
-pub fn break_repeating_key(key_size: usize, cryptotext: &[u8]) -> Vec<u8> {
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub key: u8,
+    pub score: f64,
+    pub plaintext: Vec<u8>,
+}
+
+pub fn break_repeating_key<S: Scorer>(key_size: usize, cryptotext: &[u8], scorer: &S) -> Vec<u8> {
     let mut full_key = Vec::with_capacity(key_size);
     for i in 0..key_size {
-        let (key, _) = search_single_char_key(
+        let candidates = search_single_char_key(
             &cryptotext
                 .iter()
                 .dropping(i)
                 .step(key_size)
                 .copied()
                 .collect_vec(),
+            scorer,
         );
-        full_key.push(key);
+        full_key.push(candidates[0].key);
     }
 
     full_key
 }
 
-pub fn search_single_char_key(ciphertext: &[u8]) -> (u8, u32) {
-    // For each key in 0x00 -> 0xFF
-    //  - generate key
-    //  - xor key with ciphertext
-    //  - score plaintext, store key => score mapping
-    //
-    // Find highest scoring key
-    let mut highest_score = 0;
-    let mut high_score_key = 0;
-    for ascii_key in 0..0xFF {
-        let key: Vec<u8> = vec![ascii_key];
-        let full_key = repeating_key(&key, ciphertext.len());
-
-        let plaintext = xor(ciphertext, &full_key);
-
-        let score = score_plaintext(&plaintext);
-
-        if score >= highest_score {
-            highest_score = score;
-            high_score_key = ascii_key;
-        }
-    }
-    (high_score_key, highest_score)
-}
-
-pub fn score_plaintext(plaintext: &[u8]) -> u32 {
-    plaintext
-        .iter()
-        .map(|&x| match x {
-            10 => 1,       // '\n'
-            32 => 1,       // ' '
-            39 => 1,       // '\''
-            45 => 1,       // -
-            48..=57 => 1,  // 0-9
-            58 => 1,       // :
-            65..=90 => 1,  // A-Z
-            97..=122 => 1, // a-z
-            _ => 0,
+// Tries every single byte key and returns all 256 candidates, best first.
+pub fn search_single_char_key<S: Scorer>(ciphertext: &[u8], scorer: &S) -> Vec<Candidate> {
+    let mut candidates: Vec<Candidate> = (0..=0xFF)
+        .map(|key| {
+            let full_key = repeating_key(&[key], ciphertext.len());
+            let plaintext = xor(ciphertext, &full_key);
+            let score = scorer.score(&plaintext);
+
+            Candidate {
+                key,
+                score,
+                plaintext,
+            }
         })
-        .sum()
+        .collect();
+
+    candidates.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+
+    candidates
 }
 
 pub fn hamming_distance(a: &[u8], b: &[u8]) -> u32 {
@@ -85,6 +76,8 @@ pub fn hamming_distance(a: &[u8], b: &[u8]) -> u32 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::scorer::EnglishScorer;
+    use crate::utils::{decode_b64, decode_hex, load_file};
 
     #[test]
     fn test_xor_1() {
@@ -108,6 +101,42 @@ mod tests {
         assert_eq!(res, repeating_key(&a, 6));
     }
 
+    #[test]
+    fn test_search_single_char_key_challenge_3() {
+        let ciphertext =
+            decode_hex("1b37373331363f78151b7f2b783431333d78397828372d363c78373e783a393b3736")
+                .unwrap();
+
+        let candidates = search_single_char_key(&ciphertext, &EnglishScorer::default());
+
+        assert_eq!(256, candidates.len());
+        assert_eq!(b'X', candidates[0].key);
+        assert_eq!(
+            b"Cooking MC's like a pound of bacon".to_vec(),
+            candidates[0].plaintext
+        );
+        assert!(candidates[0].score > candidates[1].score);
+    }
+
+    #[test]
+    fn test_search_single_char_key_0xff() {
+        let plaintext = b"The quick brown fox jumps over the lazy dog".to_vec();
+        let ciphertext = xor(&plaintext, &repeating_key(&[0xFF], plaintext.len()));
+
+        let candidates = search_single_char_key(&ciphertext, &EnglishScorer::default());
+
+        assert_eq!(0xFF, candidates[0].key);
+    }
+
+    #[test]
+    fn test_break_repeating_key_challenge_6() {
+        let ciphertext = decode_b64(&load_file("resources/set1/6.txt").unwrap()).unwrap();
+
+        let key = break_repeating_key(29, &ciphertext, &EnglishScorer::default());
+
+        assert_eq!(b"Terminator X: Bring the noise".to_vec(), key);
+    }
+
     #[test]
     fn test_hamming_distance() {
         let a: Vec<u8> = vec![1, 1, 1, 1];
@@ -18,7 +18,7 @@ pub fn main() {
         let b = decode_hex(&args[2]);
 
         match (a, b) {
-            (Some(a_bytes), Some(b_bytes)) => {
+            (Ok(a_bytes), Ok(b_bytes)) => {
                 let res = xor(&a_bytes, &b_bytes);
                 let encoded = encode_hex(&res);
                 println!("{:?}", encoded);
@@ -1,11 +1,9 @@
-use std::char;
 use std::env;
 use std::process;
 
+use challenge::scorer::EnglishScorer;
 use challenge::utils::decode_hex;
-use challenge::xor::repeating_key;
 use challenge::xor::search_single_char_key;
-use challenge::xor::xor;
 
 fn usage() {
     println!("Usage: xor_search_single_char_bin <hex-encoded-string>");
@@ -22,12 +20,14 @@ pub fn main() {
 
     let ciphertext = decode_hex(&args[1]).expect("Invalid hex string");
 
-    let (key, _) = search_single_char_key(&ciphertext);
-    println!("Key: {:?}", char::from_u32(key as u32).unwrap());
+    let candidates = search_single_char_key(&ciphertext, &EnglishScorer::default());
 
-    let key_vec: Vec<u8> = vec![key];
-    let full_key = repeating_key(&key_vec, ciphertext.len());
-
-    let plaintext = xor(&ciphertext, &full_key);
-    println!("Plaintext: {:?}", String::from_utf8(plaintext).unwrap());
+    for candidate in candidates.iter().take(3) {
+        println!(
+            "Key: {:?} Score: {:.3} Plaintext: {:?}",
+            candidate.key as char,
+            candidate.score,
+            String::from_utf8_lossy(&candidate.plaintext)
+        );
+    }
 }